@@ -2,4 +2,12 @@ fn main() {
     cc::Build::new()
         .file("include/sketchybar.c")
         .compile("sketchybar");
+
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("macos") {
+        cc::Build::new()
+            .file("include/power_source.c")
+            .compile("power_source");
+        println!("cargo:rustc-link-lib=framework=IOKit");
+        println!("cargo:rustc-link-lib=framework=CoreFoundation");
+    }
 }
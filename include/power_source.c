@@ -0,0 +1 @@
+#include "power_source.h"
@@ -0,0 +1,51 @@
+#pragma once
+
+#include <CoreFoundation/CoreFoundation.h>
+#include <IOKit/ps/IOPSKeys.h>
+#include <IOKit/ps/IOPowerSources.h>
+
+enum providing_power_source {
+  POWER_SOURCE_UNKNOWN = -1,
+  POWER_SOURCE_AC = 0,
+  POWER_SOURCE_BATTERY = 1,
+  POWER_SOURCE_UPS = 2,
+};
+
+int providing_power_source(void) {
+  CFTypeRef snapshot = IOPSCopyPowerSourcesInfo();
+  if (!snapshot) return POWER_SOURCE_UNKNOWN;
+
+  int source = POWER_SOURCE_UNKNOWN;
+  CFStringRef type = IOPSGetProvidingPowerSourceType(snapshot);
+  if (type) {
+    if (CFStringCompare(type, CFSTR(kIOPMACPowerKey), 0) == kCFCompareEqualTo) {
+      source = POWER_SOURCE_AC;
+    } else if (CFStringCompare(type, CFSTR(kIOPMBatteryPowerKey), 0) ==
+               kCFCompareEqualTo) {
+      source = POWER_SOURCE_BATTERY;
+    } else if (CFStringCompare(type, CFSTR(kIOPMUPSPowerKey), 0) ==
+               kCFCompareEqualTo) {
+      source = POWER_SOURCE_UPS;
+    }
+  }
+
+  CFRelease(snapshot);
+  return source;
+}
+
+// Returns the maximum power of the plugged in adapter in watts, or -1 when no
+// adapter is plugged in or it does not report its power.
+int external_adapter_watts(void) {
+  CFDictionaryRef details = IOPSCopyExternalPowerAdapterDetails();
+  if (!details) return -1;
+
+  int watts = -1;
+  CFNumberRef value =
+      CFDictionaryGetValue(details, CFSTR(kIOPSPowerAdapterWattsKey));
+  if (value && CFGetTypeID(value) == CFNumberGetTypeID()) {
+    if (!CFNumberGetValue(value, kCFNumberIntType, &watts)) watts = -1;
+  }
+
+  CFRelease(details);
+  return watts;
+}
@@ -1,5 +1,6 @@
 use anyhow::{Result, bail};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
 
 // Default values as constants
 pub const DEFAULT_INTERVAL: u32 = 5;
@@ -8,22 +9,109 @@ pub const MIN_INTERVAL: u32 = 1;
 pub const MAX_INTERVAL: u32 = 3600; // 1 hour max
 pub const MIN_NETWORK_REFRESH_RATE: u32 = 1;
 pub const MAX_NETWORK_REFRESH_RATE: u32 = 100;
-
-pub const ALL_BATTERY_FLAGS: &[&str] = &["percentage", "remaining", "state", "time_to_full"];
+pub const DEFAULT_PRECISION: u32 = 1;
+pub const MAX_PRECISION: u32 = 3;
+pub const DEFAULT_TOP_PROCESSES: u32 = 3;
+pub const MIN_TOP_PROCESSES: u32 = 1;
+pub const MAX_TOP_PROCESSES: u32 = 20;
+pub const DEFAULT_PROC_ROOT: &str = "/proc";
+pub const DEFAULT_SYS_ROOT: &str = "/sys";
+pub const DEFAULT_USAGE_RESET_DAY: u32 = 1;
+pub const MAX_USAGE_RESET_DAY: u32 = 28;
+pub const DEFAULT_BATTERY_LOW: u32 = 20;
+pub const DEFAULT_BATTERY_CRITICAL: u32 = 10;
+pub const DEFAULT_BATTERY_HYSTERESIS: u32 = 2;
+pub const MAX_BATTERY_HYSTERESIS: u32 = 20;
+
+pub const ALL_BATTERY_FLAGS: &[&str] = &[
+    "adapter_watts",
+    "capacity_wh",
+    "count",
+    "cycles",
+    "design_capacity_wh",
+    "health",
+    "model",
+    "percentage",
+    "power",
+    "power_source",
+    "remaining",
+    "state",
+    "technology",
+    "temperature",
+    "time_to_full",
+    "vendor",
+    "voltage",
+];
 pub const ALL_CPU_FLAGS: &[&str] = &["count", "frequency", "temperature", "usage"];
-pub const ALL_DISK_FLAGS: &[&str] = &["count", "free", "total", "usage", "used"];
-pub const ALL_RAM_FLAGS: &[&str] = &["ram_available", "ram_total", "ram_usage", "ram_used"];
-pub const ALL_SWP_FLAGS: &[&str] = &["swp_free", "swp_total", "swp_usage", "swp_used"];
+pub const ALL_DISK_FLAGS: &[&str] = &[
+    "count",
+    "free",
+    "read_rate",
+    "total",
+    "usage",
+    "used",
+    "write_rate",
+];
+pub const ALL_RAM_FLAGS: &[&str] = &[
+    "ram_available",
+    "ram_buffers",
+    "ram_cached",
+    "ram_dirty",
+    "ram_free",
+    "ram_shared",
+    "ram_slab",
+    "ram_total",
+    "ram_usage",
+    "ram_used",
+];
+pub const ALL_SWP_FLAGS: &[&str] = &[
+    "swp_free",
+    "swp_in_rate",
+    "swp_out_rate",
+    "swp_total",
+    "swp_usage",
+    "swp_used",
+];
 pub const ALL_MEMORY_FLAGS: &[&str] = &[
     "ram_available",
+    "ram_buffers",
+    "ram_cached",
+    "ram_dirty",
+    "ram_free",
+    "ram_shared",
+    "ram_slab",
     "ram_total",
     "ram_usage",
     "ram_used",
     "swp_free",
+    "swp_in_rate",
+    "swp_out_rate",
     "swp_total",
     "swp_usage",
     "swp_used",
 ];
+pub const ALL_NETWORK_FLAGS: &[&str] = &[
+    "errors_rx",
+    "errors_rx_rate",
+    "errors_tx",
+    "errors_tx_rate",
+    "has_address",
+    "ipv4",
+    "ipv6",
+    "mac",
+    "month",
+    "month_usage",
+    "packets_rx",
+    "packets_tx",
+    "rx",
+    "today",
+    "total_rx",
+    "total_tx",
+    "tx",
+];
+pub const DEFAULT_NETWORK_FLAGS: &[&str] = &["rx", "tx"];
+pub const ALL_PRESSURE_FLAGS: &[&str] = &["cpu", "io", "memory"];
+pub const ALL_PROCESS_FLAGS: &[&str] = &["count", "running", "threads", "top_cpu", "top_mem"];
 pub const ALL_SYSTEM_FLAGS: &[&str] = &[
     "arch",
     "distro",
@@ -35,6 +123,61 @@ pub const ALL_SYSTEM_FLAGS: &[&str] = &[
 ];
 pub const ALL_UPTIME_FLAGS: &[&str] = &["week", "day", "hour", "min", "sec"];
 
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RemovableFilter {
+    #[default]
+    Include,
+    Exclude,
+    Only,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnitSystem {
+    /// 1024-based units (KiB, MiB, GiB)
+    Iec,
+    /// 1000-based units (kB, MB, GB)
+    Si,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RateUnit {
+    /// Bytes per second (KiB/s, MiB/s)
+    #[default]
+    Bytes,
+    /// Bits per second (Kbit/s, Mbit/s, Gbit/s)
+    Bits,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DurationFormat {
+    /// Whole minutes (187min)
+    #[default]
+    Minutes,
+    /// Hours and minutes (3h 7m)
+    Human,
+    /// Hours and minutes as a clock (3:07)
+    Clock,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnavailableValue {
+    /// Leave the key out
+    #[default]
+    Omit,
+    /// Report N/A
+    Na,
+    /// Report an empty value
+    Empty,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
 #[derive(Parser, Debug)]
 #[command(name = "stats_provider", version, about, long_about = None, arg_required_else_help = true)]
 pub struct Cli {
@@ -44,18 +187,133 @@ pub struct Cli {
     #[arg(short = 'b', long, num_args = 1.., value_parser = clap::builder::PossibleValuesParser::new(ALL_BATTERY_FLAGS), help = "Get battery stats")]
     pub battery: Option<Vec<String>>,
 
+    #[arg(
+        long,
+        help = "Also emit the selected battery stats for each battery (BATTERY_<INDEX>_*)"
+    )]
+    pub per_battery: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DurationFormat::Minutes,
+        help = "Format for the battery remaining and time to full stats"
+    )]
+    pub battery_time_format: DurationFormat,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = UnavailableValue::Omit,
+        help = "What the battery remaining and time to full stats report when there is no estimate"
+    )]
+    pub battery_time_unavailable: UnavailableValue,
+
+    #[arg(
+        long,
+        help = "Trigger the battery_low, battery_critical, battery_full and power_source_changed events on battery transitions"
+    )]
+    pub battery_events: bool,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_BATTERY_LOW,
+        value_parser = clap::value_parser!(u32).range(1..=100),
+        help = "Charge percentage at or below which battery_low is triggered"
+    )]
+    pub battery_low: u32,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_BATTERY_CRITICAL,
+        value_parser = clap::value_parser!(u32).range(1..=100),
+        help = "Charge percentage at or below which battery_critical is triggered"
+    )]
+    pub battery_critical: u32,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_BATTERY_HYSTERESIS,
+        value_parser = clap::value_parser!(u32).range(0..=(MAX_BATTERY_HYSTERESIS as i64)),
+        help = "Percentage points the charge must rise above a threshold before its event can trigger again (0-20)"
+    )]
+    pub battery_hysteresis: u32,
+
     #[arg(short = 'c', long, num_args = 1.., value_parser = clap::builder::PossibleValuesParser::new(ALL_CPU_FLAGS), help = "Get CPU stats")]
     pub cpu: Option<Vec<String>>,
 
     #[arg(short = 'd', long, num_args = 1.., value_parser = clap::builder::PossibleValuesParser::new(ALL_DISK_FLAGS), help = "Get disk stats")]
     pub disk: Option<Vec<String>>,
 
+    #[arg(
+        long,
+        help = "Also emit the selected disk stats for each disk (DISK_<MOUNT>_*)"
+    )]
+    pub per_disk: bool,
+
+    #[arg(long, num_args = 1.., value_delimiter = ',', help = "Only include disks mounted at these mount points")]
+    pub disk_include_mount: Option<Vec<String>>,
+
+    #[arg(long, num_args = 1.., value_delimiter = ',', help = "Exclude disks mounted at these mount points")]
+    pub disk_exclude_mount: Option<Vec<String>>,
+
+    #[arg(long, num_args = 1.., value_delimiter = ',', help = "Only include disks with these file systems (e.g., apfs,ext4)")]
+    pub disk_include_fs: Option<Vec<String>>,
+
+    #[arg(long, num_args = 1.., value_delimiter = ',', help = "Exclude disks with these file systems")]
+    pub disk_exclude_fs: Option<Vec<String>>,
+
+    #[arg(long, value_enum, default_value_t = RemovableFilter::Include, help = "Whether removable disks are included, excluded or the only disks reported")]
+    pub disk_removable: RemovableFilter,
+
     #[arg(short = 'm', long, num_args = 1.., value_parser = clap::builder::PossibleValuesParser::new(ALL_MEMORY_FLAGS), help = "Get memory stats")]
     pub memory: Option<Vec<String>>,
 
-    #[arg(short = 'n', long, num_args = 1.., help = "Network rx/tx in KiB/s. Specify network interfaces (e.g., -n eth0 en0 lo0). At least one is required.")]
+    #[arg(short = 'n', long, num_args = 1.., help = "Network rx/tx rates. Specify network interfaces as NAME[=ALIAS][:STATS], with * and ? globs, auto for the primary interface, or total for the sum of the selected (or all) interfaces (e.g., -n en0=WIFI:rx,tx 'utun*'). At least one is required.")]
     pub network: Option<Vec<String>>,
 
+    #[arg(long, num_args = 1.., value_delimiter = ',', value_parser = clap::builder::PossibleValuesParser::new(ALL_NETWORK_FLAGS), help = "Network stats to emit for each interface [default: rx,tx]")]
+    pub network_stats: Option<Vec<String>>,
+
+    #[arg(long, num_args = 1.., value_delimiter = ',', help = "Interfaces left out of the network total, as names or * and ? globs [default: loopback and virtual interfaces]")]
+    pub network_total_exclude: Option<Vec<String>>,
+
+    #[arg(
+        long,
+        help = "Report network interfaces that do not exist yet as down instead of failing at startup"
+    )]
+    pub network_lenient: bool,
+
+    #[arg(
+        long,
+        help = "File that keeps the daily and monthly network usage across restarts"
+    )]
+    pub network_usage_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_USAGE_RESET_DAY,
+        value_parser = clap::value_parser!(u32).range(1..=(MAX_USAGE_RESET_DAY as i64)),
+        help = "Day of the month on which the monthly network usage resets (1-28)"
+    )]
+    pub network_usage_reset_day: u32,
+
+    #[arg(
+        long,
+        value_parser = parse_size,
+        help = "Monthly network data quota for the month_usage stat (e.g., 50GB, 1.5TiB)"
+    )]
+    pub network_quota: Option<u64>,
+
+    #[arg(long, num_args = 1.., value_parser = clap::builder::PossibleValuesParser::new(ALL_PRESSURE_FLAGS), help = "Get pressure stall stats (Linux only)")]
+    pub pressure: Option<Vec<String>>,
+
+    #[arg(short = 'p', long, num_args = 1.., value_parser = clap::builder::PossibleValuesParser::new(ALL_PROCESS_FLAGS), help = "Get process stats")]
+    pub processes: Option<Vec<String>>,
+
+    #[arg(long, num_args = 1.., value_delimiter = ',', help = "Watch processes by exact name or /regex/ (e.g., --watch-process docker,postgres)")]
+    pub watch_process: Option<Vec<String>>,
+
     #[arg(short = 's', long, num_args = 1.., value_parser = clap::builder::PossibleValuesParser::new(ALL_SYSTEM_FLAGS), help = "Get system stats")]
     pub system: Option<Vec<String>>,
 
@@ -79,6 +337,14 @@ pub struct Cli {
     )]
     pub network_refresh_rate: u32,
 
+    #[arg(
+        long,
+        default_value_t = DEFAULT_TOP_PROCESSES,
+        value_parser = clap::value_parser!(u32).range((MIN_TOP_PROCESSES as i64)..=(MAX_TOP_PROCESSES as i64)),
+        help = "Number of processes reported by the top_cpu and top_mem process stats (1-20)"
+    )]
+    pub top_processes: u32,
+
     #[arg(long, help = "Bar name (optional)")]
     pub bar: Option<String>,
 
@@ -87,12 +353,98 @@ pub struct Cli {
 
     #[arg(long, help = "Output values without units")]
     pub no_units: bool,
+
+    #[arg(
+        long,
+        help = "Scale sizes and rates to the best fitting unit (B, KiB, MiB, GiB, TiB)"
+    )]
+    pub auto_units: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "unit_system",
+        help = "Use 1000-based units (kB, MB, GB); same as --unit-system si"
+    )]
+    pub si: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Unit system for memory, disk and network values [default: GB and KiB/s]"
+    )]
+    pub unit_system: Option<UnitSystem>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = RateUnit::Bytes,
+        help = "Unit for network rates"
+    )]
+    pub network_rate_unit: RateUnit,
+
+    #[arg(
+        long,
+        default_value = DEFAULT_PROC_ROOT,
+        help = "Root of the proc file system, e.g. when running in a container"
+    )]
+    pub proc_root: PathBuf,
+
+    #[arg(
+        long,
+        default_value = DEFAULT_SYS_ROOT,
+        help = "Root of the sys file system batteries are read from on Linux"
+    )]
+    pub sys_root: PathBuf,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_PRECISION,
+        value_parser = clap::value_parser!(u32).range(0..=(MAX_PRECISION as i64)),
+        help = "Decimal places for --auto-units values (0-3)"
+    )]
+    pub precision: u32,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TemperatureUnit::Celsius,
+        help = "Unit for temperature stats"
+    )]
+    pub temperature_unit: TemperatureUnit,
 }
 
 pub fn parse_args() -> Cli {
     Cli::parse()
 }
 
+/// Parses a byte size such as `500MB`, `50GB` or `1.5TiB`. Plain numbers
+/// are bytes; `k`, `M`, `G` and `T` are 1000-based and `Ki`, `Mi`, `Gi` and
+/// `Ti` 1024-based.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, suffix) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{value}'"))?;
+    let multiplier: u64 = match suffix.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1_000,
+        "m" | "mb" => 1_000_000,
+        "g" | "gb" => 1_000_000_000,
+        "t" | "tb" => 1_000_000_000_000,
+        "ki" | "kib" => 1 << 10,
+        "mi" | "mib" => 1 << 20,
+        "gi" | "gib" => 1 << 30,
+        "ti" | "tib" => 1 << 40,
+        _ => return Err(format!("unknown size unit in '{value}'")),
+    };
+
+    Ok((number * multiplier as f64).round() as u64)
+}
+
 pub fn validate_cli(cli: &Cli) -> Result<()> {
     // Validate that at least one stat type is requested if not using --all
     if !cli.all
@@ -101,12 +453,30 @@ pub fn validate_cli(cli: &Cli) -> Result<()> {
         && cli.disk.is_none()
         && cli.memory.is_none()
         && cli.network.is_none()
+        && cli.pressure.is_none()
+        && cli.processes.is_none()
+        && cli.watch_process.is_none()
         && cli.system.is_none()
         && cli.uptime.is_none()
+        && !cli.battery_events
     {
         bail!("At least one stat type must be specified, or use --all");
     }
 
+    if cli.auto_units && cli.no_units {
+        // Without a unit label, an auto-scaled value such as 1.5 could be KiB
+        // or GiB.
+        bail!("--auto-units cannot be combined with --no-units");
+    }
+
+    if cli.battery_critical > cli.battery_low {
+        bail!(
+            "--battery-critical ({}) must not be above --battery-low ({})",
+            cli.battery_critical,
+            cli.battery_low
+        );
+    }
+
     Ok(())
 }
 
@@ -119,17 +489,48 @@ mod tests {
         let cli = Cli {
             all: true,
             battery: None,
+            per_battery: false,
+            battery_time_format: DurationFormat::Minutes,
+            battery_time_unavailable: UnavailableValue::Omit,
+            battery_events: false,
+            battery_low: DEFAULT_BATTERY_LOW,
+            battery_critical: DEFAULT_BATTERY_CRITICAL,
+            battery_hysteresis: DEFAULT_BATTERY_HYSTERESIS,
             cpu: None,
             disk: None,
+            per_disk: false,
+            disk_include_mount: None,
+            disk_exclude_mount: None,
+            disk_include_fs: None,
+            disk_exclude_fs: None,
+            disk_removable: RemovableFilter::Include,
             memory: None,
             network: None,
+            network_stats: None,
+            network_total_exclude: None,
+            network_lenient: false,
+            network_usage_file: None,
+            network_usage_reset_day: DEFAULT_USAGE_RESET_DAY,
+            network_quota: None,
+            pressure: None,
+            processes: None,
+            watch_process: None,
             system: None,
             uptime: None,
             interval: DEFAULT_INTERVAL,
             network_refresh_rate: DEFAULT_NETWORK_REFRESH_RATE,
+            top_processes: DEFAULT_TOP_PROCESSES,
             bar: None,
             verbose: false,
             no_units: false,
+            auto_units: false,
+            si: false,
+            unit_system: None,
+            network_rate_unit: RateUnit::Bytes,
+            proc_root: PathBuf::from(DEFAULT_PROC_ROOT),
+            sys_root: PathBuf::from(DEFAULT_SYS_ROOT),
+            precision: DEFAULT_PRECISION,
+            temperature_unit: TemperatureUnit::Celsius,
         };
         assert!(validate_cli(&cli).is_ok());
     }
@@ -139,17 +540,48 @@ mod tests {
         let cli = Cli {
             all: false,
             battery: None,
+            per_battery: false,
+            battery_time_format: DurationFormat::Minutes,
+            battery_time_unavailable: UnavailableValue::Omit,
+            battery_events: false,
+            battery_low: DEFAULT_BATTERY_LOW,
+            battery_critical: DEFAULT_BATTERY_CRITICAL,
+            battery_hysteresis: DEFAULT_BATTERY_HYSTERESIS,
             cpu: Some(vec!["usage".to_string()]),
             disk: None,
+            per_disk: false,
+            disk_include_mount: None,
+            disk_exclude_mount: None,
+            disk_include_fs: None,
+            disk_exclude_fs: None,
+            disk_removable: RemovableFilter::Include,
             memory: None,
             network: None,
+            network_stats: None,
+            network_total_exclude: None,
+            network_lenient: false,
+            network_usage_file: None,
+            network_usage_reset_day: DEFAULT_USAGE_RESET_DAY,
+            network_quota: None,
+            pressure: None,
+            processes: None,
+            watch_process: None,
             system: None,
             uptime: None,
             interval: DEFAULT_INTERVAL,
             network_refresh_rate: DEFAULT_NETWORK_REFRESH_RATE,
+            top_processes: DEFAULT_TOP_PROCESSES,
             bar: None,
             verbose: false,
             no_units: false,
+            auto_units: false,
+            si: false,
+            unit_system: None,
+            network_rate_unit: RateUnit::Bytes,
+            proc_root: PathBuf::from(DEFAULT_PROC_ROOT),
+            sys_root: PathBuf::from(DEFAULT_SYS_ROOT),
+            precision: DEFAULT_PRECISION,
+            temperature_unit: TemperatureUnit::Celsius,
         };
         assert!(validate_cli(&cli).is_ok());
     }
@@ -159,17 +591,48 @@ mod tests {
         let cli = Cli {
             all: false,
             battery: None,
+            per_battery: false,
+            battery_time_format: DurationFormat::Minutes,
+            battery_time_unavailable: UnavailableValue::Omit,
+            battery_events: false,
+            battery_low: DEFAULT_BATTERY_LOW,
+            battery_critical: DEFAULT_BATTERY_CRITICAL,
+            battery_hysteresis: DEFAULT_BATTERY_HYSTERESIS,
             cpu: None,
             disk: None,
+            per_disk: false,
+            disk_include_mount: None,
+            disk_exclude_mount: None,
+            disk_include_fs: None,
+            disk_exclude_fs: None,
+            disk_removable: RemovableFilter::Include,
             memory: None,
             network: None,
+            network_stats: None,
+            network_total_exclude: None,
+            network_lenient: false,
+            network_usage_file: None,
+            network_usage_reset_day: DEFAULT_USAGE_RESET_DAY,
+            network_quota: None,
+            pressure: None,
+            processes: None,
+            watch_process: None,
             system: None,
             uptime: None,
             interval: DEFAULT_INTERVAL,
             network_refresh_rate: DEFAULT_NETWORK_REFRESH_RATE,
+            top_processes: DEFAULT_TOP_PROCESSES,
             bar: None,
             verbose: false,
             no_units: false,
+            auto_units: false,
+            si: false,
+            unit_system: None,
+            network_rate_unit: RateUnit::Bytes,
+            proc_root: PathBuf::from(DEFAULT_PROC_ROOT),
+            sys_root: PathBuf::from(DEFAULT_SYS_ROOT),
+            precision: DEFAULT_PRECISION,
+            temperature_unit: TemperatureUnit::Celsius,
         };
         assert!(validate_cli(&cli).is_err());
     }
@@ -216,11 +679,13 @@ mod tests {
             &'static [&'static str],
             fn(&Cli) -> Option<&[String]>,
         );
-        let cases: [FlagCase; 6] = [
+        let cases: [FlagCase; 8] = [
             ("battery", ALL_BATTERY_FLAGS, |c| c.battery.as_deref()),
             ("cpu", ALL_CPU_FLAGS, |c| c.cpu.as_deref()),
             ("disk", ALL_DISK_FLAGS, |c| c.disk.as_deref()),
             ("memory", ALL_MEMORY_FLAGS, |c| c.memory.as_deref()),
+            ("pressure", ALL_PRESSURE_FLAGS, |c| c.pressure.as_deref()),
+            ("processes", ALL_PROCESS_FLAGS, |c| c.processes.as_deref()),
             ("system", ALL_SYSTEM_FLAGS, |c| c.system.as_deref()),
             ("uptime", ALL_UPTIME_FLAGS, |c| c.uptime.as_deref()),
         ];
@@ -246,13 +711,278 @@ mod tests {
 
     #[test]
     fn test_unknown_flag_values_are_rejected() {
-        for arg in ["battery", "cpu", "disk", "memory", "system", "uptime"] {
+        for arg in [
+            "battery",
+            "cpu",
+            "disk",
+            "memory",
+            "pressure",
+            "processes",
+            "system",
+            "uptime",
+        ] {
             let flag_arg = format!("--{arg}");
             let result = Cli::try_parse_from(["stats_provider", flag_arg.as_str(), "bogus"]);
             assert!(result.is_err(), "--{arg} should reject an unknown value");
         }
     }
 
+    #[test]
+    fn test_validate_cli_rejects_auto_units_without_units() {
+        let cli = Cli::try_parse_from([
+            "stats_provider",
+            "--cpu",
+            "usage",
+            "--auto-units",
+            "--no-units",
+        ])
+        .unwrap();
+
+        assert!(validate_cli(&cli).is_err());
+    }
+
+    #[test]
+    fn test_validate_cli_with_watch_process() {
+        let cli =
+            Cli::try_parse_from(["stats_provider", "--watch-process", "docker,postgres"]).unwrap();
+
+        assert_eq!(
+            cli.watch_process,
+            Some(vec!["docker".to_string(), "postgres".to_string()])
+        );
+        assert!(validate_cli(&cli).is_ok());
+    }
+
+    #[test]
+    fn test_disk_filter_options_parse() {
+        let cli = Cli::try_parse_from([
+            "stats_provider",
+            "--disk",
+            "usage",
+            "--disk-include-mount",
+            "/,/Volumes/Backup",
+            "--disk-exclude-fs",
+            "nfs",
+            "--disk-removable",
+            "exclude",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            cli.disk_include_mount,
+            Some(vec!["/".to_string(), "/Volumes/Backup".to_string()])
+        );
+        assert_eq!(cli.disk_exclude_fs, Some(vec!["nfs".to_string()]));
+        assert_eq!(cli.disk_removable, RemovableFilter::Exclude);
+        assert!(!cli.per_disk);
+    }
+
+    #[test]
+    fn test_auto_units_options_parse() {
+        let cli = Cli::try_parse_from([
+            "stats_provider",
+            "--all",
+            "--auto-units",
+            "--si",
+            "--precision",
+            "2",
+        ])
+        .unwrap();
+        assert!(cli.auto_units);
+        assert!(cli.si);
+        assert_eq!(cli.precision, 2);
+
+        let max_plus_one = (MAX_PRECISION + 1).to_string();
+        assert!(
+            Cli::try_parse_from(["stats_provider", "--precision", max_plus_one.as_str()]).is_err()
+        );
+        // `--si` is an alias for `--unit-system si`, with or without `--auto-units`.
+        let cli = Cli::try_parse_from(["stats_provider", "--all", "--si"]).unwrap();
+        assert!(cli.si);
+        assert!(!cli.auto_units);
+    }
+
+    #[test]
+    fn test_network_stats_parsing() {
+        let cli = Cli::try_parse_from(["stats_provider", "--network", "en0"]).unwrap();
+        assert_eq!(cli.network_stats, None);
+
+        let cli = Cli::try_parse_from([
+            "stats_provider",
+            "--network",
+            "en0",
+            "--network-stats",
+            "rx,total_rx,errors_rx_rate",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.network_stats,
+            Some(vec![
+                "rx".to_string(),
+                "total_rx".to_string(),
+                "errors_rx_rate".to_string()
+            ])
+        );
+
+        assert!(
+            Cli::try_parse_from(["stats_provider", "-n", "en0", "--network-stats", "bogus"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("500MB"), Ok(500_000_000));
+        assert_eq!(parse_size("50GB"), Ok(50_000_000_000));
+        assert_eq!(parse_size("50 gb"), Ok(50_000_000_000));
+        assert_eq!(parse_size("1.5TiB"), Ok(1_649_267_441_664));
+        assert_eq!(parse_size("2Gi"), Ok(2_147_483_648));
+        assert!(parse_size("lots").is_err());
+        assert!(parse_size("5XB").is_err());
+        assert!(parse_size("").is_err());
+    }
+
+    #[test]
+    fn test_network_usage_options() {
+        let cli = Cli::try_parse_from(["stats_provider", "-n", "en0"]).unwrap();
+        assert_eq!(cli.network_usage_file, None);
+        assert_eq!(cli.network_usage_reset_day, 1);
+        assert_eq!(cli.network_quota, None);
+
+        let cli = Cli::try_parse_from([
+            "stats_provider",
+            "-n",
+            "en0:today,month,month_usage",
+            "--network-usage-file",
+            "/tmp/usage.tsv",
+            "--network-usage-reset-day",
+            "15",
+            "--network-quota",
+            "20GB",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.network_usage_file,
+            Some(PathBuf::from("/tmp/usage.tsv"))
+        );
+        assert_eq!(cli.network_usage_reset_day, 15);
+        assert_eq!(cli.network_quota, Some(20_000_000_000));
+
+        for day in ["0", "29"] {
+            assert!(
+                Cli::try_parse_from(["stats_provider", "--all", "--network-usage-reset-day", day])
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_battery_event_options() {
+        let cli = Cli::try_parse_from(["stats_provider", "--battery-events"]).unwrap();
+        assert!(cli.battery_events);
+        assert_eq!(cli.battery_low, DEFAULT_BATTERY_LOW);
+        assert_eq!(cli.battery_critical, DEFAULT_BATTERY_CRITICAL);
+        assert_eq!(cli.battery_hysteresis, DEFAULT_BATTERY_HYSTERESIS);
+        assert!(validate_cli(&cli).is_ok());
+
+        let cli = Cli::try_parse_from([
+            "stats_provider",
+            "--battery-events",
+            "--battery-low",
+            "15",
+            "--battery-critical",
+            "20",
+        ])
+        .unwrap();
+        assert!(validate_cli(&cli).is_err());
+
+        assert!(
+            Cli::try_parse_from([
+                "stats_provider",
+                "--battery-events",
+                "--battery-hysteresis",
+                "21"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_proc_and_sys_root_options() {
+        let cli = Cli::try_parse_from(["stats_provider", "--all"]).unwrap();
+        assert_eq!(cli.proc_root, PathBuf::from("/proc"));
+
+        let cli =
+            Cli::try_parse_from(["stats_provider", "--all", "--proc-root", "/host/proc"]).unwrap();
+        assert_eq!(cli.proc_root, PathBuf::from("/host/proc"));
+        assert_eq!(cli.sys_root, PathBuf::from("/sys"));
+
+        let cli =
+            Cli::try_parse_from(["stats_provider", "--all", "--sys-root", "/host/sys"]).unwrap();
+        assert_eq!(cli.sys_root, PathBuf::from("/host/sys"));
+    }
+
+    #[test]
+    fn test_network_rate_unit_parsing() {
+        let cli = Cli::try_parse_from(["stats_provider", "--network", "en0"]).unwrap();
+        assert_eq!(cli.network_rate_unit, RateUnit::Bytes);
+
+        let cli = Cli::try_parse_from([
+            "stats_provider",
+            "--network",
+            "en0",
+            "--network-rate-unit",
+            "bits",
+        ])
+        .unwrap();
+        assert_eq!(cli.network_rate_unit, RateUnit::Bits);
+
+        assert!(
+            Cli::try_parse_from(["stats_provider", "--all", "--network-rate-unit", "nibbles"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_unit_system_parsing() {
+        let cli = Cli::try_parse_from(["stats_provider", "--all"]).unwrap();
+        assert_eq!(cli.unit_system, None);
+
+        for (value, expected) in [("iec", UnitSystem::Iec), ("si", UnitSystem::Si)] {
+            let cli =
+                Cli::try_parse_from(["stats_provider", "--all", "--unit-system", value]).unwrap();
+            assert_eq!(cli.unit_system, Some(expected));
+        }
+
+        assert!(
+            Cli::try_parse_from([
+                "stats_provider",
+                "--all",
+                "--auto-units",
+                "--si",
+                "--unit-system",
+                "iec"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_top_processes_range_validation_via_clap() {
+        let min = MIN_TOP_PROCESSES.to_string();
+        let max = MAX_TOP_PROCESSES.to_string();
+        let max_plus_one = (MAX_TOP_PROCESSES + 1).to_string();
+
+        assert!(Cli::try_parse_from(["stats_provider", "--top-processes", min.as_str()]).is_ok());
+        assert!(Cli::try_parse_from(["stats_provider", "--top-processes", max.as_str()]).is_ok());
+        assert!(Cli::try_parse_from(["stats_provider", "--top-processes", "0"]).is_err());
+        assert!(
+            Cli::try_parse_from(["stats_provider", "--top-processes", max_plus_one.as_str()])
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_interval_and_network_refresh_rate_defaults() {
         let cli = Cli::try_parse_from(["stats_provider", "--all"]).unwrap();
@@ -262,6 +992,21 @@ mod tests {
         assert!((MIN_INTERVAL..=MAX_INTERVAL).contains(&cli.interval));
         assert!(cli.network_refresh_rate >= MIN_NETWORK_REFRESH_RATE);
         assert!(cli.network_refresh_rate <= MAX_NETWORK_REFRESH_RATE);
+        assert_eq!(cli.temperature_unit, TemperatureUnit::Celsius);
+    }
+
+    #[test]
+    fn test_temperature_unit_parsing() {
+        for (value, expected) in [
+            ("celsius", TemperatureUnit::Celsius),
+            ("fahrenheit", TemperatureUnit::Fahrenheit),
+            ("kelvin", TemperatureUnit::Kelvin),
+        ] {
+            let cli = Cli::try_parse_from(["stats_provider", "--temperature-unit", value]).unwrap();
+            assert_eq!(cli.temperature_unit, expected);
+        }
+
+        assert!(Cli::try_parse_from(["stats_provider", "--temperature-unit", "rankine"]).is_err());
     }
 
     #[test]
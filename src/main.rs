@@ -8,8 +8,11 @@ use anyhow::{Context, Result};
 use fs2::FileExt;
 use sketchybar::Sketchybar;
 use stats::{
-    NetworkRateBaselines, get_battery_stats, get_cpu_stats, get_disk_stats, get_memory_stats,
-    get_network_stats, get_system_stats, get_uptime_stats,
+    BATTERY_EVENTS, BatteryEvents, BatteryOptions, BatteryThresholds, DiskFilter,
+    DiskRateBaselines, FormatOptions, InterfaceSpec, NetworkOptions, NetworkRateBaselines,
+    NetworkUsage, ProcessWatch, SwapRateBaselines, get_battery_stats, get_cpu_stats,
+    get_disk_stats, get_memory_stats, get_network_stats, get_pressure_stats, get_process_stats,
+    get_system_stats, get_uptime_stats, get_watched_process_stats,
 };
 use sysinfo::{Components, Disks, Networks, System};
 
@@ -18,7 +21,9 @@ struct ProcessedFlags<'a> {
     cpu_flags: Option<&'a [String]>,
     disk_flags: Option<&'a [String]>,
     memory_flags: Option<&'a [String]>,
-    network_flags: Option<&'a [String]>,
+    network_stat_flags: Option<&'a [String]>,
+    pressure_flags: Option<&'a [String]>,
+    process_flags: Option<&'a [String]>,
     uptime_flags: Option<&'a [String]>,
 }
 
@@ -36,6 +41,9 @@ impl<'a> ProcessedFlags<'a> {
     flag_refs_method!(cpu_flag_refs, cpu_flags);
     flag_refs_method!(disk_flag_refs, disk_flags);
     flag_refs_method!(memory_flag_refs, memory_flags);
+    flag_refs_method!(network_stat_flag_refs, network_stat_flags);
+    flag_refs_method!(pressure_flag_refs, pressure_flags);
+    flag_refs_method!(process_flag_refs, process_flags);
     flag_refs_method!(uptime_flag_refs, uptime_flags);
 }
 
@@ -44,11 +52,20 @@ struct StatsContext<'a> {
     disks: &'a mut Disks,
     networks: &'a mut Networks,
     components: &'a mut Components,
+    disk_baselines: DiskRateBaselines,
     network_baselines: NetworkRateBaselines,
+    swap_baselines: SwapRateBaselines,
+    battery_events: BatteryEvents,
 }
 
 struct StatsConfig<'a> {
     flags: ProcessedFlags<'a>,
+    format: FormatOptions,
+    battery: BatteryOptions,
+    disk_filter: DiskFilter,
+    network_specs: Option<Vec<InterfaceSpec>>,
+    network: NetworkOptions,
+    process_watches: Vec<ProcessWatch>,
     refresh_kind: sysinfo::RefreshKind,
 }
 
@@ -58,14 +75,81 @@ fn process_cli_flags(cli: &cli::Cli) -> ProcessedFlags<'_> {
         cpu_flags: cli.cpu.as_deref(),
         disk_flags: cli.disk.as_deref(),
         memory_flags: cli.memory.as_deref(),
-        network_flags: cli.network.as_deref(),
+        network_stat_flags: cli.network_stats.as_deref(),
+        pressure_flags: cli.pressure.as_deref(),
+        process_flags: cli.processes.as_deref(),
         uptime_flags: cli.uptime.as_deref(),
     }
 }
 
+fn build_format_options(cli: &cli::Cli) -> FormatOptions {
+    FormatOptions {
+        no_units: cli.no_units,
+        auto_units: cli.auto_units,
+        unit_system: if cli.si {
+            Some(cli::UnitSystem::Si)
+        } else {
+            cli.unit_system
+        },
+        precision: cli.precision as usize,
+    }
+}
+
+fn build_battery_options(cli: &cli::Cli) -> BatteryOptions {
+    BatteryOptions {
+        per_battery: cli.per_battery,
+        time_format: cli.battery_time_format,
+        time_unavailable: cli.battery_time_unavailable,
+        temperature_unit: cli.temperature_unit,
+        no_units: cli.no_units,
+        events: cli.battery_events.then_some(BatteryThresholds {
+            low: cli.battery_low,
+            critical: cli.battery_critical,
+            hysteresis: cli.battery_hysteresis,
+        }),
+        sys_root: cli.sys_root.clone(),
+    }
+}
+
+fn build_disk_filter(cli: &cli::Cli) -> DiskFilter {
+    DiskFilter {
+        include_mounts: cli.disk_include_mount.clone().unwrap_or_default(),
+        exclude_mounts: cli.disk_exclude_mount.clone().unwrap_or_default(),
+        include_file_systems: cli.disk_include_fs.clone().unwrap_or_default(),
+        exclude_file_systems: cli.disk_exclude_fs.clone().unwrap_or_default(),
+        removable: cli.disk_removable,
+    }
+}
+
+fn build_network_options(cli: &cli::Cli) -> Result<NetworkOptions> {
+    NetworkOptions::new(
+        cli.network_rate_unit,
+        cli.proc_root.clone(),
+        cli.network_total_exclude.as_deref(),
+        cli.network_quota,
+        cli.network_lenient,
+    )
+}
+
+fn parse_process_watches(cli: &cli::Cli) -> Result<Vec<ProcessWatch>> {
+    ProcessWatch::parse_all(cli.watch_process.as_deref().unwrap_or_default())
+}
+
+fn parse_network_specs(cli: &cli::Cli) -> Result<Option<Vec<InterfaceSpec>>> {
+    cli.network
+        .as_ref()
+        .map(|specs| {
+            specs
+                .iter()
+                .map(|spec| InterfaceSpec::parse(spec))
+                .collect()
+        })
+        .transpose()
+}
+
 fn validate_network_interfaces(
     networks: &Networks,
-    requested_interfaces: &[String],
+    requested_interfaces: &[InterfaceSpec],
     verbose: bool,
 ) -> Result<()> {
     let available_interfaces: Vec<String> = networks.keys().map(|name| name.to_string()).collect();
@@ -74,8 +158,11 @@ fn validate_network_interfaces(
         anyhow::bail!("No network interfaces available on this system");
     }
 
-    for interface in requested_interfaces {
-        if !available_interfaces.contains(interface) {
+    for interface in requested_interfaces
+        .iter()
+        .filter_map(InterfaceSpec::exact_name)
+    {
+        if !available_interfaces.iter().any(|name| name == interface) {
             let msg = format!(
                 "Network interface '{}' not found. Available interfaces: {}",
                 interface,
@@ -115,14 +202,25 @@ async fn send_initial_system_stats(
 }
 
 async fn get_stats(cli: &cli::Cli, sketchybar: &Sketchybar) -> Result<()> {
-    let refresh_kind = stats::build_refresh_kind();
+    let process_watches = parse_process_watches(cli)?;
+    let refresh_kind = stats::build_refresh_kind(
+        cli.all || cli.processes.is_some() || !process_watches.is_empty(),
+        cli.all
+            || cli
+                .processes
+                .as_ref()
+                .is_some_and(|flags| flags.iter().any(|flag| flag == "threads")),
+    );
     let mut system = System::new_with_specifics(refresh_kind);
     let mut disks = Disks::new_with_refreshed_list();
     let mut networks = Networks::new_with_refreshed_list();
     let mut components = Components::new_with_refreshed_list();
 
-    if let Some(network_flags) = &cli.network {
-        validate_network_interfaces(&networks, network_flags, cli.verbose)?;
+    let network_specs = parse_network_specs(cli)?;
+    if let Some(network_specs) = &network_specs
+        && !cli.network_lenient
+    {
+        validate_network_interfaces(&networks, network_specs, cli.verbose)?;
     }
 
     let flags = process_cli_flags(cli);
@@ -139,6 +237,12 @@ async fn get_stats(cli: &cli::Cli, sketchybar: &Sketchybar) -> Result<()> {
 
     let config = StatsConfig {
         flags,
+        format: build_format_options(cli),
+        battery: build_battery_options(cli),
+        disk_filter: build_disk_filter(cli),
+        network_specs,
+        network: build_network_options(cli)?,
+        process_watches,
         refresh_kind,
     };
 
@@ -147,7 +251,13 @@ async fn get_stats(cli: &cli::Cli, sketchybar: &Sketchybar) -> Result<()> {
         disks: &mut disks,
         networks: &mut networks,
         components: &mut components,
-        network_baselines: NetworkRateBaselines::default(),
+        disk_baselines: DiskRateBaselines::default(),
+        network_baselines: NetworkRateBaselines::with_usage(NetworkUsage::load(
+            cli.network_usage_file.clone(),
+            cli.network_usage_reset_day,
+        )?),
+        swap_baselines: SwapRateBaselines::default(),
+        battery_events: BatteryEvents::default(),
     };
 
     run_stats_loop(cli, sketchybar, &config, &mut context, &mut message_buffer).await
@@ -178,6 +288,9 @@ async fn run_stats_loop(
                 if cli.verbose {
                     println!("Received shutdown signal, cleaning up...");
                 }
+                if let Err(err) = context.network_baselines.usage_mut().save() {
+                    eprintln!("Warning: {err:#}");
+                }
                 println!("SketchyBar Stats Provider is shutting down.");
                 return Ok(());
             }
@@ -202,6 +315,12 @@ async fn collect_and_send_stats(
         .send_message("trigger", "system_stats", Some(buf), cli.verbose)
         .await?;
 
+    for event in context.battery_events.take_pending() {
+        sketchybar
+            .send_message("trigger", event.name, Some(&event.payload), cli.verbose)
+            .await?;
+    }
+
     tokio::time::sleep(tokio::time::Duration::from_secs(cli.interval.into())).await;
 
     Ok(updated_tick)
@@ -233,8 +352,15 @@ fn collect_stats_commands(
     } else {
         config.flags.battery_flag_refs()
     };
+    // Battery events need readings even when no battery stats are emitted.
+    let battery_flags = battery_flags.or_else(|| config.battery.events.map(|_| Vec::new()));
     if let Some(battery_flags) = battery_flags {
-        get_battery_stats(&battery_flags, cli.no_units, buf);
+        get_battery_stats(
+            &battery_flags,
+            &config.battery,
+            &mut context.battery_events,
+            buf,
+        );
     }
 
     let cpu_flags: Option<Vec<&str>> = if cli.all {
@@ -247,6 +373,7 @@ fn collect_stats_commands(
             context.system,
             context.components,
             &cpu_flags,
+            cli.temperature_unit,
             cli.no_units,
             buf,
         );
@@ -258,7 +385,15 @@ fn collect_stats_commands(
         config.flags.disk_flag_refs()
     };
     if let Some(disk_flags) = disk_flags {
-        get_disk_stats(context.disks, &disk_flags, cli.no_units, buf);
+        get_disk_stats(
+            context.disks,
+            &disk_flags,
+            &config.disk_filter,
+            cli.per_disk,
+            &mut context.disk_baselines,
+            &config.format,
+            buf,
+        );
     }
 
     let memory_flags: Option<Vec<&str>> = if cli.all {
@@ -267,23 +402,70 @@ fn collect_stats_commands(
         config.flags.memory_flag_refs()
     };
     if let Some(memory_flags) = memory_flags {
-        get_memory_stats(context.system, &memory_flags, cli.no_units, buf);
+        get_memory_stats(
+            context.system,
+            &memory_flags,
+            &mut context.swap_baselines,
+            &config.format,
+            &cli.proc_root,
+            buf,
+        );
     }
 
-    let network_interfaces: Option<&[String]> = if cli.all {
+    let network_interfaces: Option<&[InterfaceSpec]> = if cli.all {
         None
     } else {
-        config.flags.network_flags
+        config.network_specs.as_deref()
     };
     if cli.all || network_interfaces.is_some() {
+        let network_stat_flags: Vec<&str> = if cli.all {
+            cli::ALL_NETWORK_FLAGS.to_vec()
+        } else {
+            config
+                .flags
+                .network_stat_flag_refs()
+                .unwrap_or_else(|| cli::DEFAULT_NETWORK_FLAGS.to_vec())
+        };
         get_network_stats(
             context.networks,
             network_interfaces,
+            &network_stat_flags,
             &mut context.network_baselines,
+            &config.format,
+            &config.network,
+            buf,
+        );
+        if let Err(err) = context.network_baselines.usage_mut().save_if_due() {
+            eprintln!("Warning: {err:#}");
+        }
+    }
+
+    let pressure_flags: Option<Vec<&str>> = if cli.all {
+        Some(cli::ALL_PRESSURE_FLAGS.to_vec())
+    } else {
+        config.flags.pressure_flag_refs()
+    };
+    if let Some(pressure_flags) = pressure_flags {
+        get_pressure_stats(&pressure_flags, &cli.proc_root, cli.no_units, buf);
+    }
+
+    let process_flags: Option<Vec<&str>> = if cli.all {
+        Some(cli::ALL_PROCESS_FLAGS.to_vec())
+    } else {
+        config.flags.process_flag_refs()
+    };
+    if let Some(process_flags) = process_flags {
+        get_process_stats(
+            context.system,
+            &process_flags,
+            cli.top_processes as usize,
             cli.no_units,
             buf,
         );
     }
+    if !config.process_watches.is_empty() {
+        get_watched_process_stats(context.system, &config.process_watches, cli.no_units, buf);
+    }
 
     let uptime_flags: Option<Vec<&str>> = if cli.all {
         Some(cli::ALL_UPTIME_FLAGS.to_vec())
@@ -334,6 +516,13 @@ async fn main() -> Result<()> {
     sketchybar
         .send_message("add event", "system_stats", None, cli.verbose)
         .await?;
+    if cli.battery_events {
+        for event in BATTERY_EVENTS {
+            sketchybar
+                .send_message("add event", event, None, cli.verbose)
+                .await?;
+        }
+    }
 
     get_stats(&cli, &sketchybar).await?;
 
@@ -362,17 +551,48 @@ mod tests {
         let cli = cli::Cli {
             all: false,
             battery: None,
+            per_battery: false,
+            battery_time_format: cli::DurationFormat::Minutes,
+            battery_time_unavailable: cli::UnavailableValue::Omit,
+            battery_events: false,
+            battery_low: cli::DEFAULT_BATTERY_LOW,
+            battery_critical: cli::DEFAULT_BATTERY_CRITICAL,
+            battery_hysteresis: cli::DEFAULT_BATTERY_HYSTERESIS,
             cpu: Some(vec!["usage".to_string()]),
             disk: None,
+            per_disk: false,
+            disk_include_mount: None,
+            disk_exclude_mount: None,
+            disk_include_fs: None,
+            disk_exclude_fs: None,
+            disk_removable: cli::RemovableFilter::Include,
             memory: Some(vec!["ram_total".to_string()]),
             network: None,
+            network_stats: None,
+            network_total_exclude: None,
+            network_lenient: false,
+            network_usage_file: None,
+            network_usage_reset_day: cli::DEFAULT_USAGE_RESET_DAY,
+            network_quota: None,
+            pressure: None,
+            processes: None,
+            watch_process: None,
             system: None,
             uptime: None,
             interval: 5,
             network_refresh_rate: 5,
+            top_processes: cli::DEFAULT_TOP_PROCESSES,
             bar: None,
             verbose: false,
             no_units: false,
+            auto_units: false,
+            si: false,
+            unit_system: None,
+            network_rate_unit: cli::RateUnit::Bytes,
+            proc_root: cli::DEFAULT_PROC_ROOT.into(),
+            sys_root: cli::DEFAULT_SYS_ROOT.into(),
+            precision: cli::DEFAULT_PRECISION,
+            temperature_unit: cli::TemperatureUnit::Celsius,
         };
 
         let flags = process_cli_flags(&cli);
@@ -380,7 +600,7 @@ mod tests {
         assert!(flags.cpu_flags.is_some());
         assert!(flags.disk_flags.is_none());
         assert!(flags.memory_flags.is_some());
-        assert!(flags.network_flags.is_none());
+        assert!(flags.network_stat_flags.is_none());
     }
 
     #[test]
@@ -391,7 +611,9 @@ mod tests {
             cpu_flags: Some(&cpu_flags),
             disk_flags: None,
             memory_flags: None,
-            network_flags: None,
+            network_stat_flags: None,
+            pressure_flags: None,
+            process_flags: None,
             uptime_flags: None,
         };
 
@@ -410,20 +632,64 @@ mod tests {
             cpu_flags: None,
             disk_flags: None,
             memory_flags: None,
-            network_flags: None,
+            network_stat_flags: None,
+            pressure_flags: None,
+            process_flags: None,
             uptime_flags: None,
         };
 
         assert!(flags.cpu_flag_refs().is_none());
         assert!(flags.disk_flag_refs().is_none());
         assert!(flags.memory_flag_refs().is_none());
+        assert!(flags.pressure_flag_refs().is_none());
+        assert!(flags.process_flag_refs().is_none());
         assert!(flags.uptime_flag_refs().is_none());
     }
 
+    #[test]
+    fn test_parse_process_watches() {
+        use clap::Parser;
+
+        let cli =
+            cli::Cli::try_parse_from(["stats_provider", "--watch-process", "docker,/^postgres/"])
+                .unwrap();
+        assert_eq!(parse_process_watches(&cli).unwrap().len(), 2);
+
+        let invalid =
+            cli::Cli::try_parse_from(["stats_provider", "--watch-process", "/[/"]).unwrap();
+        assert!(parse_process_watches(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_network_specs() {
+        use clap::Parser;
+
+        let cli = cli::Cli::try_parse_from(["stats_provider", "--cpu", "usage"]).unwrap();
+        assert!(parse_network_specs(&cli).unwrap().is_none());
+
+        let cli =
+            cli::Cli::try_parse_from(["stats_provider", "-n", "en0=WIFI:rx,tx", "utun*"]).unwrap();
+        assert_eq!(parse_network_specs(&cli).unwrap().unwrap().len(), 2);
+
+        let invalid = cli::Cli::try_parse_from(["stats_provider", "-n", "en0:bogus"]).unwrap();
+        assert!(parse_network_specs(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_validate_network_interfaces_ignores_globs() {
+        let networks = Networks::new_with_refreshed_list();
+        if networks.is_empty() {
+            return;
+        }
+        let requested = vec![InterfaceSpec::parse("definitely-not-an-interface-*").unwrap()];
+
+        assert!(validate_network_interfaces(&networks, &requested, false).is_ok());
+    }
+
     #[test]
     fn test_validate_network_interfaces_rejects_unknown() {
         let networks = Networks::new_with_refreshed_list();
-        let requested = vec!["definitely-not-an-interface-xyz".to_string()];
+        let requested = vec![InterfaceSpec::parse("definitely-not-an-interface-xyz").unwrap()];
 
         let result = validate_network_interfaces(&networks, &requested, false);
 
@@ -435,24 +701,61 @@ mod tests {
         let cli = cli::Cli {
             all: true,
             battery: None,
+            per_battery: false,
+            battery_time_format: cli::DurationFormat::Minutes,
+            battery_time_unavailable: cli::UnavailableValue::Omit,
+            battery_events: false,
+            battery_low: cli::DEFAULT_BATTERY_LOW,
+            battery_critical: cli::DEFAULT_BATTERY_CRITICAL,
+            battery_hysteresis: cli::DEFAULT_BATTERY_HYSTERESIS,
             cpu: None,
             disk: None,
+            per_disk: false,
+            disk_include_mount: None,
+            disk_exclude_mount: None,
+            disk_include_fs: None,
+            disk_exclude_fs: None,
+            disk_removable: cli::RemovableFilter::Include,
             memory: None,
             network: None,
+            network_stats: None,
+            network_total_exclude: None,
+            network_lenient: false,
+            network_usage_file: None,
+            network_usage_reset_day: cli::DEFAULT_USAGE_RESET_DAY,
+            network_quota: None,
+            pressure: None,
+            processes: None,
+            watch_process: None,
             system: None,
             uptime: None,
             interval: 5,
             network_refresh_rate: 5,
+            top_processes: cli::DEFAULT_TOP_PROCESSES,
             bar: None,
             verbose: false,
             no_units: false,
+            auto_units: false,
+            si: false,
+            unit_system: None,
+            network_rate_unit: cli::RateUnit::Bytes,
+            proc_root: cli::DEFAULT_PROC_ROOT.into(),
+            sys_root: cli::DEFAULT_SYS_ROOT.into(),
+            precision: cli::DEFAULT_PRECISION,
+            temperature_unit: cli::TemperatureUnit::Celsius,
         };
         let flags = process_cli_flags(&cli);
         let config = StatsConfig {
             flags,
-            refresh_kind: stats::build_refresh_kind(),
+            format: build_format_options(&cli),
+            battery: build_battery_options(&cli),
+            disk_filter: DiskFilter::default(),
+            network_specs: None,
+            network: NetworkOptions::default(),
+            process_watches: Vec::new(),
+            refresh_kind: stats::build_refresh_kind(cli.all, cli.all),
         };
-        let mut system = System::new_with_specifics(stats::build_refresh_kind());
+        let mut system = System::new_with_specifics(stats::build_refresh_kind(cli.all, cli.all));
         let mut disks = Disks::new_with_refreshed_list();
         let mut networks = Networks::new_with_refreshed_list();
         let mut components = Components::new_with_refreshed_list();
@@ -461,7 +764,10 @@ mod tests {
             disks: &mut disks,
             networks: &mut networks,
             components: &mut components,
+            disk_baselines: DiskRateBaselines::default(),
             network_baselines: NetworkRateBaselines::default(),
+            swap_baselines: SwapRateBaselines::default(),
+            battery_events: BatteryEvents::default(),
         };
         let mut buf = String::new();
 
@@ -477,6 +783,8 @@ mod tests {
             "DISK_TOTAL=",
             "DISK_USED=",
             "DISK_USAGE=",
+            "DISK_READ_RATE=",
+            "DISK_WRITE_RATE=",
         ] {
             assert!(buf.contains(key), "missing disk key {key} in: {buf}");
         }
@@ -492,9 +800,29 @@ mod tests {
         ] {
             assert!(buf.contains(key), "missing memory key {key} in: {buf}");
         }
+        for key in [
+            "PROC_COUNT=",
+            "PROC_RUNNING=",
+            "PROC_THREADS=",
+            "PROC_TOP_CPU_1_NAME=",
+        ] {
+            assert!(buf.contains(key), "missing process key {key} in: {buf}");
+        }
         assert!(buf.contains("UPTIME=\""), "missing uptime in: {buf}");
         assert!(buf.contains("NETWORK_RX_"), "missing network rx in: {buf}");
         assert!(buf.contains("NETWORK_TX_"), "missing network tx in: {buf}");
+        assert!(
+            buf.contains("NETWORK_RX_TOTAL="),
+            "missing network total in: {buf}"
+        );
+        assert!(
+            buf.contains("NETWORK_TOTAL_RX_"),
+            "missing network totals in: {buf}"
+        );
+        assert!(
+            buf.contains("NETWORK_ERRORS_TX_RATE_"),
+            "missing network error rates in: {buf}"
+        );
 
         // System stats are startup-only (send_initial_system_stats), so the
         // per-tick buffer must not contain any system keys even with --all.
@@ -536,24 +864,61 @@ mod tests {
         let cli = cli::Cli {
             all: true,
             battery: None,
+            per_battery: false,
+            battery_time_format: cli::DurationFormat::Minutes,
+            battery_time_unavailable: cli::UnavailableValue::Omit,
+            battery_events: false,
+            battery_low: cli::DEFAULT_BATTERY_LOW,
+            battery_critical: cli::DEFAULT_BATTERY_CRITICAL,
+            battery_hysteresis: cli::DEFAULT_BATTERY_HYSTERESIS,
             cpu: None,
             disk: None,
+            per_disk: false,
+            disk_include_mount: None,
+            disk_exclude_mount: None,
+            disk_include_fs: None,
+            disk_exclude_fs: None,
+            disk_removable: cli::RemovableFilter::Include,
             memory: None,
             network: None,
+            network_stats: None,
+            network_total_exclude: None,
+            network_lenient: false,
+            network_usage_file: None,
+            network_usage_reset_day: cli::DEFAULT_USAGE_RESET_DAY,
+            network_quota: None,
+            pressure: None,
+            processes: None,
+            watch_process: None,
             system: None,
             uptime: None,
             interval: 5,
             network_refresh_rate: 5,
+            top_processes: cli::DEFAULT_TOP_PROCESSES,
             bar: None,
             verbose: false,
             no_units: false,
+            auto_units: false,
+            si: false,
+            unit_system: None,
+            network_rate_unit: cli::RateUnit::Bytes,
+            proc_root: cli::DEFAULT_PROC_ROOT.into(),
+            sys_root: cli::DEFAULT_SYS_ROOT.into(),
+            precision: cli::DEFAULT_PRECISION,
+            temperature_unit: cli::TemperatureUnit::Celsius,
         };
         let flags = process_cli_flags(&cli);
         let config = StatsConfig {
             flags,
-            refresh_kind: stats::build_refresh_kind(),
+            format: build_format_options(&cli),
+            battery: build_battery_options(&cli),
+            disk_filter: DiskFilter::default(),
+            network_specs: None,
+            network: NetworkOptions::default(),
+            process_watches: Vec::new(),
+            refresh_kind: stats::build_refresh_kind(cli.all, cli.all),
         };
-        let mut system = System::new_with_specifics(stats::build_refresh_kind());
+        let mut system = System::new_with_specifics(stats::build_refresh_kind(cli.all, cli.all));
         let mut disks = Disks::new_with_refreshed_list();
         let mut networks = Networks::new_with_refreshed_list();
         let mut components = Components::new_with_refreshed_list();
@@ -562,7 +927,10 @@ mod tests {
             disks: &mut disks,
             networks: &mut networks,
             components: &mut components,
+            disk_baselines: DiskRateBaselines::default(),
             network_baselines: NetworkRateBaselines::default(),
+            swap_baselines: SwapRateBaselines::default(),
+            battery_events: BatteryEvents::default(),
         };
         let mut buf = String::new();
 
@@ -1,53 +1,544 @@
-use super::{PERCENT, SECONDS_PER_MINUTE, unit};
-use starship_battery::{Manager, State};
-use std::fmt::Write;
-
-pub fn get_battery_stats(flags: &[&str], no_units: bool, buf: &mut String) {
-    let manager = match Manager::new() {
-        Ok(m) => m,
-        Err(_) => return,
-    };
+use super::power_supply::{SupplyBattery, read_power_supplies};
+use super::uptime::format_duration;
+use super::{
+    KELVIN_OFFSET, PERCENT, SECONDS_PER_MINUTE, convert_temperature, strip_quotes,
+    temperature_suffix, unit,
+};
+use crate::cli::{DEFAULT_SYS_ROOT, DurationFormat, TemperatureUnit, UnavailableValue};
+use starship_battery::{Battery, Manager, State, Technology};
+use std::fmt::{self, Write};
+use std::path::PathBuf;
 
-    let batteries: Vec<_> = match manager.batteries() {
-        Ok(batteries) => batteries.filter_map(Result::ok).collect(),
-        Err(_) => return,
-    };
+/// Joules in a watt-hour.
+const JOULES_PER_WH: f32 = 3600.0;
 
-    if batteries.is_empty() {
-        return;
+/// Micro units (µWh, µW, µV) in a unit, as reported by sysfs.
+const MICROS_PER_UNIT: f32 = 1_000_000.0;
+
+/// Sketchybar events triggered by `BatteryEvents`.
+pub const BATTERY_EVENTS: &[&str] = &[
+    "battery_low",
+    "battery_critical",
+    "battery_full",
+    "power_source_changed",
+];
+
+/// How battery stats are read and reported.
+#[derive(Clone, Debug)]
+pub struct BatteryOptions {
+    pub per_battery: bool,
+    pub time_format: DurationFormat,
+    pub time_unavailable: UnavailableValue,
+    pub temperature_unit: TemperatureUnit,
+    pub no_units: bool,
+    /// Thresholds for the battery events, or `None` when they are disabled.
+    pub events: Option<BatteryThresholds>,
+    /// Root of the sys file system the batteries are read from on Linux.
+    pub sys_root: PathBuf,
+}
+
+impl Default for BatteryOptions {
+    fn default() -> Self {
+        Self {
+            per_battery: false,
+            time_format: DurationFormat::default(),
+            time_unavailable: UnavailableValue::default(),
+            temperature_unit: TemperatureUnit::default(),
+            no_units: false,
+            events: None,
+            sys_root: PathBuf::from(DEFAULT_SYS_ROOT),
+        }
+    }
+}
+
+/// Charge percentages at which `battery_low` and `battery_critical` trigger.
+#[derive(Clone, Copy, Debug)]
+pub struct BatteryThresholds {
+    pub low: u32,
+    pub critical: u32,
+    /// Percentage points the charge must rise above a threshold before its
+    /// event can trigger again, so a charge hovering around the threshold
+    /// does not trigger it repeatedly.
+    pub hysteresis: u32,
+}
+
+/// A battery event to trigger, with the battery stats passed along with it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BatteryEvent {
+    pub name: &'static str,
+    pub payload: String,
+}
+
+/// Tracks consecutive battery readings and queues an event whenever the
+/// battery crosses a threshold or the machine changes power source.
+#[derive(Debug, Default)]
+pub struct BatteryEvents {
+    low: bool,
+    critical: bool,
+    full: Option<bool>,
+    power_source: Option<PowerSource>,
+    pending: Vec<BatteryEvent>,
+}
+
+impl BatteryEvents {
+    /// Compares `battery` and `power_source` with the previous reading and
+    /// queues the events they trigger. Without a battery, only the power
+    /// source is tracked.
+    ///
+    /// `battery_low` and `battery_critical` also trigger on the first reading
+    /// when the charge is already below the threshold, while `battery_full`
+    /// and `power_source_changed` only trigger on a change.
+    fn update(
+        &mut self,
+        battery: Option<&BatteryReading>,
+        power_source: PowerSource,
+        options: &BatteryOptions,
+    ) {
+        let Some(thresholds) = options.events else {
+            return;
+        };
+
+        let mut names = Vec::new();
+        if let Some(battery) = battery {
+            let percentage = (battery.state_of_charge * PERCENT).round() as u32;
+            let low = crossed(self.low, percentage, thresholds.low, thresholds.hysteresis);
+            let critical = crossed(
+                self.critical,
+                percentage,
+                thresholds.critical,
+                thresholds.hysteresis,
+            );
+            let full = battery.state == State::Full;
+
+            if low && !self.low {
+                names.push("battery_low");
+            }
+            if critical && !self.critical {
+                names.push("battery_critical");
+            }
+            if self.full == Some(false) && full {
+                names.push("battery_full");
+            }
+
+            self.low = low;
+            self.critical = critical;
+            self.full = Some(full);
+        }
+        if self
+            .power_source
+            .is_some_and(|previous| previous != power_source)
+        {
+            names.push("power_source_changed");
+        }
+        self.power_source = Some(power_source);
+
+        for name in names {
+            let mut payload = String::new();
+            if let Some(battery) = battery {
+                write_battery_stats(
+                    &mut payload,
+                    "BATTERY",
+                    &["percentage", "state"],
+                    battery,
+                    options,
+                );
+            }
+            let _ = write!(payload, "BATTERY_POWER_SOURCE=\"{power_source}\" ");
+            self.pending.push(BatteryEvent { name, payload });
+        }
+    }
+
+    /// Returns the events queued since the previous call.
+    pub fn take_pending(&mut self) -> Vec<BatteryEvent> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// Returns whether the charge is below `threshold`, given whether it already
+/// was: once below, it only leaves after rising `hysteresis` points above it.
+fn crossed(was_below: bool, percentage: u32, threshold: u32, hysteresis: u32) -> bool {
+    if was_below {
+        percentage < threshold + hysteresis
+    } else {
+        percentage <= threshold
     }
+}
+
+/// Where the machine draws its power from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PowerSource {
+    Ac,
+    Battery,
+    Ups,
+}
+
+impl fmt::Display for PowerSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ac => "ac",
+            Self::Battery => "battery",
+            Self::Ups => "ups",
+        })
+    }
+}
+
+/// The machine's external power, as far as the platform reports it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ExternalPower {
+    /// Whether an AC adapter is plugged in, when the platform reports adapters.
+    ac_online: Option<bool>,
+    /// Whether a UPS is powering the machine.
+    on_ups: bool,
+    /// Maximum power of the plugged in adapter.
+    adapter_watts: Option<f32>,
+}
+
+impl ExternalPower {
+    /// Returns the power source. Where adapters are not reported, the machine
+    /// is on AC unless its battery discharges, so a desktop without a battery
+    /// reports AC.
+    fn power_source(&self, battery: Option<&BatteryReading>) -> PowerSource {
+        if self.on_ups {
+            return PowerSource::Ups;
+        }
+        let on_ac = self
+            .ac_online
+            .unwrap_or_else(|| battery.is_none_or(|battery| battery.state != State::Discharging));
+        if on_ac {
+            PowerSource::Ac
+        } else {
+            PowerSource::Battery
+        }
+    }
+}
+
+/// One battery's reading, or the aggregate of several batteries.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct BatteryReading {
+    /// Charge as a fraction between 0 and 1.
+    state_of_charge: f32,
+    energy_wh: f32,
+    energy_full_wh: f32,
+    /// Charge or discharge power in watts, always positive.
+    energy_rate_w: f32,
+    state: State,
+    time_to_empty_secs: Option<u64>,
+    time_to_full_secs: Option<u64>,
+    /// Full capacity relative to the design capacity, between 0 and 1.
+    state_of_health: f32,
+    energy_full_design_wh: f32,
+    cycle_count: Option<u32>,
+    vendor: Option<String>,
+    model: Option<String>,
+    technology: Technology,
+    /// Power in watts, positive while charging and negative while discharging.
+    power_w: f32,
+    voltage_v: f32,
+    temperature_celsius: Option<f32>,
+}
+
+impl BatteryReading {
+    fn from_battery(battery: &Battery) -> Self {
+        Self {
+            state_of_charge: battery.state_of_charge().value,
+            energy_wh: battery.energy().value / JOULES_PER_WH,
+            energy_full_wh: battery.energy_full().value / JOULES_PER_WH,
+            energy_rate_w: battery.energy_rate().value.abs(),
+            state: battery.state(),
+            time_to_empty_secs: battery.time_to_empty().map(|time| time.value as u64),
+            time_to_full_secs: battery.time_to_full().map(|time| time.value as u64),
+            state_of_health: battery.state_of_health().value,
+            energy_full_design_wh: battery.energy_full_design().value / JOULES_PER_WH,
+            cycle_count: battery.cycle_count(),
+            vendor: battery.vendor().map(str::to_owned),
+            model: battery.model().map(str::to_owned),
+            technology: battery.technology(),
+            power_w: signed_power(battery.energy_rate().value.abs(), battery.state()),
+            voltage_v: battery.voltage().value,
+            temperature_celsius: battery
+                .temperature()
+                .map(|temperature| temperature.value - KELVIN_OFFSET),
+        }
+    }
+
+    fn from_supply(supply: &SupplyBattery) -> Self {
+        let units = |micros: Option<u64>| micros.map(|micros| micros as f32 / MICROS_PER_UNIT);
+        let state = match supply.status.as_deref() {
+            Some("Charging") => State::Charging,
+            Some("Discharging") => State::Discharging,
+            Some("Full") => State::Full,
+            Some("Empty") => State::Empty,
+            _ => State::Unknown,
+        };
+        let energy_wh = units(supply.energy_now_uwh);
+        let energy_full_wh = units(supply.energy_full_uwh);
+        let energy_full_design_wh = units(supply.energy_full_design_uwh);
+        let energy_rate_w = units(supply.power_now_uw).unwrap_or_default();
+
+        let state_of_charge = match (energy_wh, energy_full_wh) {
+            (Some(energy), Some(full)) if full > 0.0 => (energy / full).min(1.0),
+            _ => supply
+                .capacity
+                .map_or(0.0, |capacity| capacity as f32 / PERCENT),
+        };
+        let state_of_health = match (energy_full_wh, energy_full_design_wh) {
+            (Some(full), Some(design)) if design > 0.0 => full / design,
+            _ => 1.0,
+        };
+        let energy_wh = energy_wh.unwrap_or_default();
+        let energy_full_wh = energy_full_wh.unwrap_or_default();
+        let (time_to_empty_secs, time_to_full_secs) =
+            estimate_times(state, energy_wh, energy_full_wh, energy_rate_w);
+
+        Self {
+            state_of_charge,
+            energy_wh,
+            energy_full_wh,
+            energy_rate_w,
+            state,
+            time_to_empty_secs,
+            time_to_full_secs,
+            state_of_health,
+            energy_full_design_wh: energy_full_design_wh.unwrap_or_default(),
+            cycle_count: supply.cycle_count,
+            vendor: supply.manufacturer.clone(),
+            model: supply.model_name.clone(),
+            technology: supply
+                .technology
+                .as_deref()
+                .and_then(|technology| technology.parse().ok())
+                .unwrap_or_default(),
+            power_w: signed_power(energy_rate_w, state),
+            voltage_v: units(supply.voltage_now_uv).unwrap_or_default(),
+            temperature_celsius: supply
+                .temp_decicelsius
+                .map(|decicelsius| decicelsius as f32 / 10.0),
+        }
+    }
+
+    /// Combines several batteries into one reading.
+    ///
+    /// The charge is weighted by energy rather than averaged, so a small
+    /// secondary pack does not skew it, and the times come from the combined
+    /// energy and power. The health compares the combined full and design
+    /// capacities, the cycle count is that of the most worn battery, and the
+    /// vendor, model and technology are those of the first battery. Power is
+    /// summed, the voltage averaged and the temperature is the hottest one.
+    fn aggregate(readings: &[Self]) -> Option<Self> {
+        match readings {
+            [] => return None,
+            [reading] => return Some(reading.clone()),
+            _ => {}
+        }
 
-    let battery = &batteries[0];
+        let energy_wh: f32 = readings.iter().map(|r| r.energy_wh).sum();
+        let energy_full_wh: f32 = readings.iter().map(|r| r.energy_full_wh).sum();
+        // Sum the signed power, so a pack charging from another that
+        // discharges nets out instead of adding up.
+        let power_w: f32 = readings.iter().map(|r| r.power_w).sum();
+        let energy_rate_w = power_w.abs();
+        let energy_full_design_wh: f32 = readings.iter().map(|r| r.energy_full_design_wh).sum();
+        let state_of_charge = if energy_full_wh > 0.0 {
+            energy_wh / energy_full_wh
+        } else {
+            readings.iter().map(|r| r.state_of_charge).sum::<f32>() / readings.len() as f32
+        };
 
+        let state_of_health = if energy_full_design_wh > 0.0 {
+            energy_full_wh / energy_full_design_wh
+        } else {
+            readings.iter().map(|r| r.state_of_health).sum::<f32>() / readings.len() as f32
+        };
+
+        let any = |state: State| readings.iter().any(|r| r.state == state);
+        let all = |state: State| readings.iter().all(|r| r.state == state);
+        let state = if any(State::Charging) && any(State::Discharging) {
+            if power_w < 0.0 {
+                State::Discharging
+            } else {
+                State::Charging
+            }
+        } else if any(State::Charging) {
+            State::Charging
+        } else if any(State::Discharging) {
+            State::Discharging
+        } else if all(State::Full) {
+            State::Full
+        } else if all(State::Empty) {
+            State::Empty
+        } else {
+            State::Unknown
+        };
+
+        let (time_to_empty_secs, time_to_full_secs) =
+            estimate_times(state, energy_wh, energy_full_wh, energy_rate_w);
+
+        Some(Self {
+            state_of_charge,
+            energy_wh,
+            energy_full_wh,
+            energy_rate_w,
+            state,
+            time_to_empty_secs,
+            time_to_full_secs,
+            state_of_health,
+            energy_full_design_wh,
+            cycle_count: readings.iter().filter_map(|r| r.cycle_count).max(),
+            power_w,
+            voltage_v: readings.iter().map(|r| r.voltage_v).sum::<f32>() / readings.len() as f32,
+            temperature_celsius: readings
+                .iter()
+                .filter_map(|r| r.temperature_celsius)
+                .max_by(f32::total_cmp),
+            ..readings[0].clone()
+        })
+    }
+}
+
+/// Estimates the seconds until the battery is empty and until it is full
+/// from its energy and power, for whichever applies to `state`.
+fn estimate_times(
+    state: State,
+    energy_wh: f32,
+    energy_full_wh: f32,
+    energy_rate_w: f32,
+) -> (Option<u64>, Option<u64>) {
+    let hours_to_secs = |hours: f32| (hours * JOULES_PER_WH) as u64;
+    let time_to_empty_secs = (state == State::Discharging && energy_rate_w > 0.0)
+        .then(|| hours_to_secs(energy_wh / energy_rate_w));
+    let time_to_full_secs = (state == State::Charging && energy_rate_w > 0.0)
+        .then(|| hours_to_secs((energy_full_wh - energy_wh).max(0.0) / energy_rate_w));
+    (time_to_empty_secs, time_to_full_secs)
+}
+
+/// Returns the power flowing into the battery: the magnitude reported by the
+/// battery, negated while it discharges.
+fn signed_power(energy_rate_w: f32, state: State) -> f32 {
+    if state == State::Discharging {
+        -energy_rate_w
+    } else {
+        energy_rate_w
+    }
+}
+
+/// Formats a battery time estimate in `format`.
+fn format_battery_time(secs: u64, format: DurationFormat, no_units: bool) -> String {
+    let minutes = secs / SECONDS_PER_MINUTE;
+    match format {
+        DurationFormat::Minutes => format!("{minutes}{}", unit(no_units, "min")),
+        DurationFormat::Human => format_duration(secs, &["hour", "min"]),
+        DurationFormat::Clock => format!("{}:{:02}", minutes / 60, minutes % 60),
+    }
+}
+
+/// Writes a battery time estimate, or the configured placeholder when the
+/// battery has no estimate.
+fn write_battery_time(buf: &mut String, key: &str, secs: Option<u64>, options: &BatteryOptions) {
+    let value = match (secs, options.time_unavailable) {
+        (Some(secs), _) => format_battery_time(secs, options.time_format, options.no_units),
+        (None, UnavailableValue::Omit) => return,
+        (None, UnavailableValue::Na) => "N/A".to_string(),
+        (None, UnavailableValue::Empty) => String::new(),
+    };
+    let _ = write!(buf, "{key}=\"{value}\" ");
+}
+
+/// Writes the selected `flags` for one battery reading under `prefix`.
+fn write_battery_stats(
+    buf: &mut String,
+    prefix: &str,
+    flags: &[&str],
+    battery: &BatteryReading,
+    options: &BatteryOptions,
+) {
+    let BatteryOptions {
+        temperature_unit,
+        no_units,
+        ..
+    } = *options;
     for &flag in flags {
         match flag {
             "percentage" => {
-                let percentage = (battery.state_of_charge().value * PERCENT).round() as u32;
+                let percentage = (battery.state_of_charge * PERCENT).round() as u32;
                 let unit = unit(no_units, "%");
-                let _ = write!(buf, "BATTERY_PERCENTAGE=\"{percentage}{unit}\" ");
+                let _ = write!(buf, "{prefix}_PERCENTAGE=\"{percentage}{unit}\" ");
             }
             "state" => {
-                let state_str = match battery.state() {
+                let state_str = match battery.state {
                     State::Charging => "charging",
                     State::Discharging => "discharging",
                     State::Full => "full",
                     State::Empty => "empty",
                     _ => "unknown",
                 };
-                let _ = write!(buf, "BATTERY_STATE=\"{state_str}\" ");
+                let _ = write!(buf, "{prefix}_STATE=\"{state_str}\" ");
             }
             "remaining" => {
-                if let Some(time) = battery.time_to_empty() {
-                    let mins = time.value as u64 / SECONDS_PER_MINUTE;
-                    let unit = unit(no_units, "min");
-                    let _ = write!(buf, "BATTERY_REMAINING=\"{mins}{unit}\" ");
-                }
+                let key = format!("{prefix}_REMAINING");
+                write_battery_time(buf, &key, battery.time_to_empty_secs, options);
             }
             "time_to_full" => {
-                if let Some(time) = battery.time_to_full() {
-                    let mins = time.value as u64 / SECONDS_PER_MINUTE;
-                    let unit = unit(no_units, "min");
-                    let _ = write!(buf, "BATTERY_TIME_TO_FULL=\"{mins}{unit}\" ");
+                let key = format!("{prefix}_TIME_TO_FULL");
+                write_battery_time(buf, &key, battery.time_to_full_secs, options);
+            }
+            "health" => {
+                let health = (battery.state_of_health * PERCENT).round() as u32;
+                let unit = unit(no_units, "%");
+                let _ = write!(buf, "{prefix}_HEALTH=\"{health}{unit}\" ");
+            }
+            "cycles" => match battery.cycle_count {
+                Some(cycles) => {
+                    let _ = write!(buf, "{prefix}_CYCLES=\"{cycles}\" ");
+                }
+                None => {
+                    let _ = write!(buf, "{prefix}_CYCLES=\"N/A\" ");
+                }
+            },
+            "capacity_wh" => {
+                let unit = unit(no_units, "Wh");
+                let _ = write!(
+                    buf,
+                    "{prefix}_CAPACITY_WH=\"{:.1}{unit}\" ",
+                    battery.energy_full_wh
+                );
+            }
+            "design_capacity_wh" => {
+                let unit = unit(no_units, "Wh");
+                let _ = write!(
+                    buf,
+                    "{prefix}_DESIGN_CAPACITY_WH=\"{:.1}{unit}\" ",
+                    battery.energy_full_design_wh
+                );
+            }
+            "vendor" => {
+                let vendor = battery_label(battery.vendor.as_deref());
+                let _ = write!(buf, "{prefix}_VENDOR=\"{vendor}\" ");
+            }
+            "model" => {
+                let model = battery_label(battery.model.as_deref());
+                let _ = write!(buf, "{prefix}_MODEL=\"{model}\" ");
+            }
+            "technology" => {
+                let _ = write!(buf, "{prefix}_TECHNOLOGY=\"{}\" ", battery.technology);
+            }
+            "power" => {
+                let unit = unit(no_units, "W");
+                let _ = write!(buf, "{prefix}_POWER=\"{:.1}{unit}\" ", battery.power_w);
+            }
+            "voltage" => {
+                let unit = unit(no_units, "V");
+                let _ = write!(buf, "{prefix}_VOLTAGE=\"{:.2}{unit}\" ", battery.voltage_v);
+            }
+            "temperature" => {
+                let unit = unit(no_units, temperature_suffix(temperature_unit));
+                match battery.temperature_celsius {
+                    Some(celsius) => {
+                        let temp = convert_temperature(celsius, temperature_unit);
+                        let _ = write!(buf, "{prefix}_TEMP=\"{temp:.1}{unit}\" ");
+                    }
+                    None => {
+                        let _ = write!(buf, "{prefix}_TEMP=\"N/A{unit}\" ");
+                    }
                 }
             }
             _ => {}
@@ -55,14 +546,158 @@ pub fn get_battery_stats(flags: &[&str], no_units: bool, buf: &mut String) {
     }
 }
 
+/// Returns a vendor or model string, or `N/A` when it is not reported.
+fn battery_label(label: Option<&str>) -> String {
+    match label.map(str::trim) {
+        Some(label) if !label.is_empty() => strip_quotes(label),
+        _ => "N/A".to_string(),
+    }
+}
+
+/// Writes the machine-wide battery stats (count, power source and adapter),
+/// the aggregate battery stats and, with `per_battery`, the stats of each
+/// battery as `BATTERY_<INDEX>_*`.
+fn write_all_battery_stats(
+    buf: &mut String,
+    flags: &[&str],
+    readings: &[BatteryReading],
+    external: &ExternalPower,
+    options: &BatteryOptions,
+) {
+    let aggregate = BatteryReading::aggregate(readings);
+
+    for &flag in flags {
+        match flag {
+            "count" => {
+                let _ = write!(buf, "BATTERY_COUNT=\"{}\" ", readings.len());
+            }
+            "power_source" => {
+                let source = external.power_source(aggregate.as_ref());
+                let _ = write!(buf, "BATTERY_POWER_SOURCE=\"{source}\" ");
+            }
+            "adapter_watts" => match external.adapter_watts {
+                Some(watts) => {
+                    let unit = unit(options.no_units, "W");
+                    let _ = write!(buf, "BATTERY_ADAPTER_WATTS=\"{watts:.0}{unit}\" ");
+                }
+                None => {
+                    let _ = write!(buf, "BATTERY_ADAPTER_WATTS=\"N/A\" ");
+                }
+            },
+            _ => {}
+        }
+    }
+
+    let Some(aggregate) = aggregate else {
+        return;
+    };
+    write_battery_stats(buf, "BATTERY", flags, &aggregate, options);
+
+    if options.per_battery {
+        for (index, reading) in readings.iter().enumerate() {
+            write_battery_stats(buf, &format!("BATTERY_{index}"), flags, reading, options);
+        }
+    }
+}
+
+/// Reads the external power from IOKit, which reports the adapter whatever
+/// the charge state, e.g. while a full battery is not charging.
+#[cfg(target_os = "macos")]
+fn read_external_power() -> ExternalPower {
+    use super::iokit::{self, ProvidingSource};
+
+    let source = iokit::providing_source();
+    ExternalPower {
+        ac_online: source.map(|source| source == ProvidingSource::Ac),
+        on_ups: source == Some(ProvidingSource::Ups),
+        adapter_watts: iokit::adapter_watts().map(|watts| watts as f32),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn read_external_power() -> ExternalPower {
+    ExternalPower::default()
+}
+
+pub fn get_battery_stats(
+    flags: &[&str],
+    options: &BatteryOptions,
+    events: &mut BatteryEvents,
+    buf: &mut String,
+) {
+    // On Linux, sysfs also reports AC adapters and UPSes, which
+    // starship-battery does not expose.
+    let (readings, external) = if cfg!(target_os = "linux") {
+        let supplies = read_power_supplies(&options.sys_root);
+        let readings = supplies
+            .batteries
+            .iter()
+            .map(BatteryReading::from_supply)
+            .collect();
+        let external = ExternalPower {
+            ac_online: supplies.ac_online,
+            on_ups: supplies.on_ups,
+            adapter_watts: supplies
+                .adapter_power_uw
+                .map(|micros| micros as f32 / MICROS_PER_UNIT),
+        };
+        (readings, external)
+    } else {
+        (read_batteries(), read_external_power())
+    };
+
+    write_all_battery_stats(buf, flags, &readings, &external, options);
+    let aggregate = BatteryReading::aggregate(&readings);
+    let power_source = external.power_source(aggregate.as_ref());
+    events.update(aggregate.as_ref(), power_source, options);
+}
+
+/// Reads every battery through starship-battery.
+fn read_batteries() -> Vec<BatteryReading> {
+    let Ok(manager) = Manager::new() else {
+        return Vec::new();
+    };
+
+    match manager.batteries() {
+        Ok(batteries) => batteries
+            .filter_map(Result::ok)
+            .map(|battery| BatteryReading::from_battery(&battery))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn reading(energy_wh: f32, energy_full_wh: f32, state: State) -> BatteryReading {
+        BatteryReading {
+            state_of_charge: energy_wh / energy_full_wh,
+            energy_wh,
+            energy_full_wh,
+            energy_rate_w: 0.0,
+            state,
+            ..BatteryReading::default()
+        }
+    }
+
+    fn per_battery() -> BatteryOptions {
+        BatteryOptions {
+            per_battery: true,
+            ..BatteryOptions::default()
+        }
+    }
+
     #[test]
     fn test_get_battery_stats_with_units() {
         let mut buf = String::new();
-        get_battery_stats(&["percentage", "state"], false, &mut buf);
+        get_battery_stats(
+            &["percentage", "state"],
+            &BatteryOptions::default(),
+            &mut BatteryEvents::default(),
+            &mut buf,
+        );
 
         if !buf.is_empty() {
             assert!(buf.contains("BATTERY_PERCENTAGE="));
@@ -84,7 +719,16 @@ mod tests {
     #[test]
     fn test_get_battery_stats_without_units() {
         let mut buf = String::new();
-        get_battery_stats(&["percentage"], true, &mut buf);
+        let options = BatteryOptions {
+            no_units: true,
+            ..BatteryOptions::default()
+        };
+        get_battery_stats(
+            &["percentage"],
+            &options,
+            &mut BatteryEvents::default(),
+            &mut buf,
+        );
 
         if buf.contains("BATTERY_PERCENTAGE=") {
             assert!(!buf.contains("%"));
@@ -94,8 +738,608 @@ mod tests {
     #[test]
     fn test_get_battery_stats_empty_flags() {
         let mut buf = String::new();
-        get_battery_stats(&[], false, &mut buf);
+        let options = BatteryOptions {
+            per_battery: true,
+            ..BatteryOptions::default()
+        };
+        get_battery_stats(&[], &options, &mut BatteryEvents::default(), &mut buf);
 
         assert_eq!(buf, "");
     }
+
+    #[test]
+    fn test_aggregate_weights_charge_by_energy() {
+        // A big pack at 90% and a small one at 10%: averaging would give 50%.
+        let readings = [
+            reading(72.0, 80.0, State::Discharging),
+            reading(2.0, 20.0, State::Full),
+        ];
+
+        let aggregate = BatteryReading::aggregate(&readings).unwrap();
+
+        assert!((aggregate.state_of_charge - 0.74).abs() < 1e-6);
+        assert_eq!(aggregate.energy_full_wh, 100.0);
+        assert_eq!(aggregate.state, State::Discharging);
+    }
+
+    #[test]
+    fn test_aggregate_times_use_combined_energy_and_power() {
+        let mut first = reading(30.0, 50.0, State::Discharging);
+        first.power_w = -10.0;
+        let mut second = reading(30.0, 50.0, State::Discharging);
+        second.power_w = -5.0;
+
+        let discharging = BatteryReading::aggregate(&[first.clone(), second.clone()]).unwrap();
+        assert_eq!(discharging.energy_rate_w, 15.0);
+        assert_eq!(discharging.time_to_empty_secs, Some(4 * 3600));
+        assert_eq!(discharging.time_to_full_secs, None);
+
+        // One pack charging at 10 W from another discharging at 5 W nets 5 W.
+        first.state = State::Charging;
+        first.power_w = 10.0;
+        let charging = BatteryReading::aggregate(&[first.clone(), second.clone()]).unwrap();
+        assert_eq!(charging.energy_rate_w, 5.0);
+        assert_eq!(charging.state, State::Charging);
+        assert_eq!(charging.time_to_empty_secs, None);
+        assert_eq!(charging.time_to_full_secs, Some(8 * 3600));
+
+        second.power_w = -25.0;
+        let net_discharging = BatteryReading::aggregate(&[first, second]).unwrap();
+        assert_eq!(net_discharging.energy_rate_w, 15.0);
+        assert_eq!(net_discharging.state, State::Discharging);
+        assert_eq!(net_discharging.time_to_empty_secs, Some(4 * 3600));
+    }
+
+    #[test]
+    fn test_aggregate_state() {
+        let full = reading(50.0, 50.0, State::Full);
+        let empty = reading(0.0, 50.0, State::Empty);
+
+        let state =
+            |readings: &[BatteryReading]| BatteryReading::aggregate(readings).unwrap().state;
+        assert_eq!(state(&[full.clone(), full.clone()]), State::Full);
+        assert_eq!(state(&[empty.clone(), empty.clone()]), State::Empty);
+        assert_eq!(state(&[full, empty]), State::Unknown);
+        assert!(BatteryReading::aggregate(&[]).is_none());
+    }
+
+    #[test]
+    fn test_aggregate_single_battery_is_unchanged() {
+        let mut single = reading(40.0, 50.0, State::Discharging);
+        single.time_to_empty_secs = Some(11_220);
+
+        assert_eq!(BatteryReading::aggregate(&[single.clone()]), Some(single));
+    }
+
+    #[test]
+    fn test_write_all_battery_stats_count_and_per_battery_keys() {
+        let readings = [
+            reading(40.0, 50.0, State::Discharging),
+            reading(10.0, 50.0, State::Discharging),
+        ];
+        let mut buf = String::new();
+
+        write_all_battery_stats(
+            &mut buf,
+            &["count", "percentage"],
+            &readings,
+            &ExternalPower::default(),
+            &per_battery(),
+        );
+
+        assert_eq!(
+            buf,
+            "BATTERY_COUNT=\"2\" BATTERY_PERCENTAGE=\"50%\" \
+             BATTERY_0_PERCENTAGE=\"80%\" BATTERY_1_PERCENTAGE=\"20%\" "
+        );
+    }
+
+    #[test]
+    fn test_write_all_battery_stats_without_per_battery() {
+        let readings = [
+            reading(40.0, 50.0, State::Charging),
+            reading(10.0, 50.0, State::Full),
+        ];
+        let mut buf = String::new();
+
+        write_all_battery_stats(
+            &mut buf,
+            &["state"],
+            &readings,
+            &ExternalPower::default(),
+            &BatteryOptions::default(),
+        );
+
+        assert_eq!(buf, "BATTERY_STATE=\"charging\" ");
+    }
+
+    #[test]
+    fn test_write_all_battery_stats_no_batteries() {
+        let mut buf = String::new();
+
+        write_all_battery_stats(
+            &mut buf,
+            crate::cli::ALL_BATTERY_FLAGS,
+            &[],
+            &ExternalPower::default(),
+            &per_battery(),
+        );
+
+        // A desktop without a battery still reports its power source, and a
+        // zero count replaces the one of a removed battery.
+        assert_eq!(
+            buf,
+            "BATTERY_ADAPTER_WATTS=\"N/A\" BATTERY_COUNT=\"0\" BATTERY_POWER_SOURCE=\"ac\" "
+        );
+    }
+
+    #[test]
+    fn test_aggregate_health_and_cycles() {
+        let mut first = reading(40.0, 45.0, State::Discharging);
+        first.energy_full_design_wh = 50.0;
+        first.cycle_count = Some(120);
+        first.vendor = Some("SMP".to_string());
+        first.technology = Technology::LithiumIon;
+        let mut second = reading(10.0, 15.0, State::Discharging);
+        second.energy_full_design_wh = 25.0;
+        second.cycle_count = Some(310);
+        second.vendor = Some("Sunwoda".to_string());
+
+        let aggregate = BatteryReading::aggregate(&[first, second]).unwrap();
+
+        assert!((aggregate.state_of_health - 0.8).abs() < 1e-6);
+        assert_eq!(aggregate.energy_full_design_wh, 75.0);
+        assert_eq!(aggregate.cycle_count, Some(310));
+        assert_eq!(aggregate.vendor.as_deref(), Some("SMP"));
+        assert_eq!(aggregate.technology, Technology::LithiumIon);
+    }
+
+    #[test]
+    fn test_write_battery_stats_wear_flags() {
+        let mut battery = reading(40.0, 52.63, State::Full);
+        battery.state_of_health = 0.923;
+        battery.energy_full_design_wh = 57.0;
+        battery.model = Some("bq40z651 \"A2519\"".to_string());
+        battery.technology = Technology::LithiumIon;
+        let mut buf = String::new();
+
+        write_battery_stats(
+            &mut buf,
+            "BATTERY",
+            &[
+                "health",
+                "cycles",
+                "capacity_wh",
+                "design_capacity_wh",
+                "vendor",
+                "model",
+                "technology",
+            ],
+            &battery,
+            &BatteryOptions::default(),
+        );
+
+        assert_eq!(
+            buf,
+            "BATTERY_HEALTH=\"92%\" BATTERY_CYCLES=\"N/A\" BATTERY_CAPACITY_WH=\"52.6Wh\" \
+             BATTERY_DESIGN_CAPACITY_WH=\"57.0Wh\" BATTERY_VENDOR=\"N/A\" \
+             BATTERY_MODEL=\"bq40z651 A2519\" BATTERY_TECHNOLOGY=\"lithium-ion\" "
+        );
+    }
+
+    #[test]
+    fn test_signed_power() {
+        assert_eq!(signed_power(12.5, State::Discharging), -12.5);
+        assert_eq!(signed_power(30.0, State::Charging), 30.0);
+        assert_eq!(signed_power(0.0, State::Full), 0.0);
+    }
+
+    #[test]
+    fn test_aggregate_power_voltage_and_temperature() {
+        let mut first = reading(40.0, 50.0, State::Charging);
+        first.power_w = 20.0;
+        first.voltage_v = 12.0;
+        first.temperature_celsius = Some(31.0);
+        let mut second = reading(20.0, 50.0, State::Discharging);
+        second.power_w = -5.0;
+        second.voltage_v = 11.0;
+
+        let aggregate = BatteryReading::aggregate(&[first, second]).unwrap();
+
+        assert_eq!(aggregate.power_w, 15.0);
+        assert_eq!(aggregate.voltage_v, 11.5);
+        assert_eq!(aggregate.temperature_celsius, Some(31.0));
+    }
+
+    #[test]
+    fn test_write_battery_stats_power_voltage_and_temperature() {
+        let mut battery = reading(40.0, 50.0, State::Discharging);
+        battery.power_w = -12.34;
+        battery.voltage_v = 12.456;
+        battery.temperature_celsius = Some(30.0);
+        let flags = ["power", "voltage", "temperature"];
+
+        let mut buf = String::new();
+        write_battery_stats(
+            &mut buf,
+            "BATTERY",
+            &flags,
+            &battery,
+            &BatteryOptions {
+                temperature_unit: TemperatureUnit::Fahrenheit,
+                ..BatteryOptions::default()
+            },
+        );
+        assert_eq!(
+            buf,
+            "BATTERY_POWER=\"-12.3W\" BATTERY_VOLTAGE=\"12.46V\" BATTERY_TEMP=\"86.0°F\" "
+        );
+
+        battery.temperature_celsius = None;
+        let mut buf = String::new();
+        write_battery_stats(
+            &mut buf,
+            "BATTERY",
+            &flags,
+            &battery,
+            &BatteryOptions {
+                no_units: true,
+                ..BatteryOptions::default()
+            },
+        );
+        assert_eq!(
+            buf,
+            "BATTERY_POWER=\"-12.3\" BATTERY_VOLTAGE=\"12.46\" BATTERY_TEMP=\"N/A\" "
+        );
+    }
+
+    #[test]
+    fn test_format_battery_time() {
+        let secs = 3 * 3600 + 7 * 60 + 42;
+
+        assert_eq!(
+            format_battery_time(secs, DurationFormat::Minutes, false),
+            "187min"
+        );
+        assert_eq!(
+            format_battery_time(secs, DurationFormat::Minutes, true),
+            "187"
+        );
+        assert_eq!(
+            format_battery_time(secs, DurationFormat::Human, false),
+            "3h 7m"
+        );
+        assert_eq!(
+            format_battery_time(secs, DurationFormat::Clock, false),
+            "3:07"
+        );
+        assert_eq!(format_battery_time(59, DurationFormat::Human, false), "0m");
+        assert_eq!(
+            format_battery_time(59, DurationFormat::Clock, false),
+            "0:00"
+        );
+    }
+
+    #[test]
+    fn test_write_battery_stats_unavailable_times() {
+        let battery = reading(40.0, 50.0, State::Unknown);
+        let flags = ["remaining", "time_to_full"];
+        let write = |time_unavailable| {
+            let mut buf = String::new();
+            let options = BatteryOptions {
+                time_unavailable,
+                ..BatteryOptions::default()
+            };
+            write_battery_stats(&mut buf, "BATTERY", &flags, &battery, &options);
+            buf
+        };
+
+        assert_eq!(write(UnavailableValue::Omit), "");
+        assert_eq!(
+            write(UnavailableValue::Na),
+            "BATTERY_REMAINING=\"N/A\" BATTERY_TIME_TO_FULL=\"N/A\" "
+        );
+        assert_eq!(
+            write(UnavailableValue::Empty),
+            "BATTERY_REMAINING=\"\" BATTERY_TIME_TO_FULL=\"\" "
+        );
+    }
+
+    #[test]
+    fn test_write_battery_stats_time_format() {
+        let mut battery = reading(40.0, 50.0, State::Discharging);
+        battery.time_to_empty_secs = Some(11_220);
+        let options = BatteryOptions {
+            time_format: DurationFormat::Clock,
+            ..BatteryOptions::default()
+        };
+        let mut buf = String::new();
+
+        write_battery_stats(&mut buf, "BATTERY", &["remaining"], &battery, &options);
+
+        assert_eq!(buf, "BATTERY_REMAINING=\"3:07\" ");
+    }
+
+    fn with_events() -> BatteryOptions {
+        BatteryOptions {
+            events: Some(BatteryThresholds {
+                low: 20,
+                critical: 10,
+                hysteresis: 2,
+            }),
+            ..BatteryOptions::default()
+        }
+    }
+
+    /// Feeds `readings` through a tracker and returns the names of the events
+    /// each reading triggered.
+    fn event_names(readings: &[(f32, State)]) -> Vec<Vec<&'static str>> {
+        let mut events = BatteryEvents::default();
+        readings
+            .iter()
+            .map(|&(energy_wh, state)| {
+                let battery = reading(energy_wh, 100.0, state);
+                let source = ExternalPower::default().power_source(Some(&battery));
+                events.update(Some(&battery), source, &with_events());
+                events
+                    .take_pending()
+                    .into_iter()
+                    .map(|event| event.name)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_crossed_hysteresis() {
+        assert!(crossed(false, 20, 20, 2));
+        assert!(!crossed(false, 21, 20, 2));
+        assert!(crossed(true, 21, 20, 2));
+        assert!(!crossed(true, 22, 20, 2));
+        assert!(!crossed(true, 21, 20, 0));
+    }
+
+    #[test]
+    fn test_battery_events_trigger_once_on_thresholds() {
+        use State::Discharging;
+
+        let names = event_names(&[
+            (25.0, Discharging),
+            (20.0, Discharging),
+            (19.0, Discharging),
+            (21.0, Discharging),
+            (20.0, Discharging),
+            (10.0, Discharging),
+            (9.0, Discharging),
+        ]);
+
+        assert_eq!(
+            names,
+            vec![
+                vec![],
+                vec!["battery_low"],
+                vec![],
+                // Within the hysteresis band: still low, no new event.
+                vec![],
+                vec![],
+                vec!["battery_critical"],
+                vec![],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_battery_events_rearm_after_hysteresis() {
+        use State::{Charging, Discharging};
+
+        let names = event_names(&[(20.0, Discharging), (22.0, Charging), (20.0, Discharging)]);
+
+        assert_eq!(
+            names,
+            vec![
+                vec!["battery_low"],
+                vec!["power_source_changed"],
+                vec!["battery_low", "power_source_changed"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_battery_events_full_and_first_reading() {
+        use State::{Charging, Full};
+
+        // Already low at startup: battery_low triggers right away, but the
+        // first reading never counts as a power source change.
+        assert_eq!(
+            event_names(&[(5.0, Charging)]),
+            vec![vec!["battery_low", "battery_critical"]]
+        );
+        assert_eq!(
+            event_names(&[
+                (100.0, Full),
+                (99.0, Charging),
+                (100.0, Full),
+                (100.0, Full)
+            ]),
+            vec![vec![], vec![], vec!["battery_full"], vec![]]
+        );
+    }
+
+    #[test]
+    fn test_battery_events_payload_and_disabled() {
+        let battery = reading(9.0, 100.0, State::Discharging);
+
+        let mut events = BatteryEvents::default();
+        events.update(Some(&battery), PowerSource::Battery, &with_events());
+        assert_eq!(
+            events.take_pending(),
+            vec![
+                BatteryEvent {
+                    name: "battery_low",
+                    payload: "BATTERY_PERCENTAGE=\"9%\" BATTERY_STATE=\"discharging\" \
+                              BATTERY_POWER_SOURCE=\"battery\" "
+                        .to_string(),
+                },
+                BatteryEvent {
+                    name: "battery_critical",
+                    payload: "BATTERY_PERCENTAGE=\"9%\" BATTERY_STATE=\"discharging\" \
+                              BATTERY_POWER_SOURCE=\"battery\" "
+                        .to_string(),
+                },
+            ]
+        );
+        assert!(events.take_pending().is_empty());
+
+        let mut disabled = BatteryEvents::default();
+        disabled.update(
+            Some(&battery),
+            PowerSource::Battery,
+            &BatteryOptions::default(),
+        );
+        assert!(disabled.take_pending().is_empty());
+    }
+
+    #[test]
+    fn test_battery_events_follow_power_source() {
+        let battery = reading(100.0, 100.0, State::Full);
+        let mut events = BatteryEvents::default();
+
+        events.update(Some(&battery), PowerSource::Ac, &with_events());
+        events.update(Some(&battery), PowerSource::Battery, &with_events());
+        // Without a battery, a UPS taking over still triggers the event.
+        events.update(None, PowerSource::Ups, &with_events());
+
+        let pending = events.take_pending();
+        let names: Vec<_> = pending.iter().map(|e| e.name).collect();
+        assert_eq!(names, vec!["power_source_changed", "power_source_changed"]);
+        assert_eq!(pending[1].payload, "BATTERY_POWER_SOURCE=\"ups\" ");
+    }
+
+    #[test]
+    fn test_power_source() {
+        let discharging = reading(40.0, 50.0, State::Discharging);
+        let full = reading(50.0, 50.0, State::Full);
+        let unreported = ExternalPower::default();
+        let unplugged = ExternalPower {
+            ac_online: Some(false),
+            ..ExternalPower::default()
+        };
+        let ups = ExternalPower {
+            on_ups: true,
+            ..unplugged
+        };
+
+        assert_eq!(
+            unreported.power_source(Some(&discharging)),
+            PowerSource::Battery
+        );
+        assert_eq!(unreported.power_source(Some(&full)), PowerSource::Ac);
+        assert_eq!(unreported.power_source(None), PowerSource::Ac);
+        assert_eq!(unplugged.power_source(Some(&full)), PowerSource::Battery);
+        assert_eq!(ups.power_source(None), PowerSource::Ups);
+    }
+
+    #[test]
+    fn test_write_all_battery_stats_power_source_and_adapter() {
+        let readings = [reading(50.0, 50.0, State::Full)];
+        let external = ExternalPower {
+            ac_online: Some(true),
+            on_ups: false,
+            adapter_watts: Some(65.0),
+        };
+        let mut buf = String::new();
+
+        write_all_battery_stats(
+            &mut buf,
+            &["power_source", "adapter_watts", "state"],
+            &readings,
+            &external,
+            &BatteryOptions::default(),
+        );
+
+        assert_eq!(
+            buf,
+            "BATTERY_POWER_SOURCE=\"ac\" BATTERY_ADAPTER_WATTS=\"65W\" BATTERY_STATE=\"full\" "
+        );
+    }
+
+    #[test]
+    fn test_from_supply() {
+        let supply = SupplyBattery {
+            status: Some("Discharging".to_string()),
+            capacity: Some(79),
+            energy_now_uwh: Some(40_000_000),
+            energy_full_uwh: Some(50_000_000),
+            energy_full_design_uwh: Some(62_500_000),
+            power_now_uw: Some(10_000_000),
+            voltage_now_uv: Some(12_456_000),
+            temp_decicelsius: Some(305),
+            cycle_count: Some(312),
+            manufacturer: Some("SMP".to_string()),
+            model_name: None,
+            technology: Some("Li-poly".to_string()),
+        };
+
+        let battery = BatteryReading::from_supply(&supply);
+
+        assert_eq!(battery.state_of_charge, 0.8);
+        assert_eq!(battery.state_of_health, 0.8);
+        assert_eq!(battery.state, State::Discharging);
+        assert_eq!(battery.time_to_empty_secs, Some(4 * 3600));
+        assert_eq!(battery.time_to_full_secs, None);
+        assert_eq!(battery.power_w, -10.0);
+        assert_eq!(battery.temperature_celsius, Some(30.5));
+        assert_eq!(battery.technology, Technology::LithiumPolymer);
+    }
+
+    #[test]
+    fn test_from_supply_falls_back_to_capacity() {
+        let supply = SupplyBattery {
+            status: Some("Not charging".to_string()),
+            capacity: Some(64),
+            ..SupplyBattery::default()
+        };
+
+        let battery = BatteryReading::from_supply(&supply);
+
+        assert_eq!(battery.state_of_charge, 0.64);
+        assert_eq!(battery.state, State::Unknown);
+        assert_eq!(battery.time_to_empty_secs, None);
+        assert_eq!(battery.technology, Technology::Unknown);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_get_battery_stats_reads_sysfs() {
+        use crate::stats::power_supply::tests::{LAPTOP, fixture};
+
+        let sys_root = fixture("battery_stats", LAPTOP);
+        let options = BatteryOptions {
+            sys_root: sys_root.clone(),
+            ..BatteryOptions::default()
+        };
+        let mut buf = String::new();
+
+        get_battery_stats(
+            &[
+                "count",
+                "power_source",
+                "percentage",
+                "state",
+                "remaining",
+                "cycles",
+            ],
+            &options,
+            &mut BatteryEvents::default(),
+            &mut buf,
+        );
+
+        assert_eq!(
+            buf,
+            "BATTERY_COUNT=\"1\" BATTERY_POWER_SOURCE=\"battery\" BATTERY_PERCENTAGE=\"80%\" BATTERY_STATE=\"discharging\" \
+             BATTERY_REMAINING=\"192min\" BATTERY_CYCLES=\"312\" "
+        );
+
+        std::fs::remove_dir_all(&sys_root).unwrap();
+    }
 }
@@ -1,9 +1,18 @@
+use crate::cli::TemperatureUnit;
+use std::collections::HashSet;
+
 /// Bytes in one gibibyte (1024^3), used to convert byte counts to gigabytes.
 pub const BYTES_PER_GB: f32 = 1_073_741_824.0;
 
 /// Bytes in one kibibyte (1024), used to convert byte counts to kibibytes.
 pub const BYTES_PER_KB: u64 = 1024;
 
+/// Bytes in one SI gigabyte (1000^3), used with `--unit-system si`.
+pub const BYTES_PER_GB_SI: f32 = 1_000_000_000.0;
+
+/// Bytes in one SI kilobyte (1000), used with `--unit-system si`.
+pub const BYTES_PER_KB_SI: u64 = 1000;
+
 /// Factor used to scale a ratio in 0 to 1 into a percentage.
 pub const PERCENT: f32 = 100.0;
 
@@ -13,6 +22,66 @@ pub const SECONDS_PER_MINUTE: u64 = 60;
 /// Temperature sentinel returned when no CPU temperature component is found.
 pub const NO_TEMP_SENTINEL: f32 = -1.0;
 
+/// Offset between the Celsius and Kelvin scales.
+pub const KELVIN_OFFSET: f32 = 273.15;
+
+/// Converts a temperature reported in degrees Celsius into `temperature_unit`.
+pub fn convert_temperature(celsius: f32, temperature_unit: TemperatureUnit) -> f32 {
+    match temperature_unit {
+        TemperatureUnit::Celsius => celsius,
+        TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        TemperatureUnit::Kelvin => celsius + KELVIN_OFFSET,
+    }
+}
+
+/// Returns the unit suffix for `temperature_unit`.
+pub fn temperature_suffix(temperature_unit: TemperatureUnit) -> &'static str {
+    match temperature_unit {
+        TemperatureUnit::Celsius => "°C",
+        TemperatureUnit::Fahrenheit => "°F",
+        TemperatureUnit::Kelvin => "K",
+    }
+}
+
+/// Converts a user-facing name into an uppercase sketchybar key fragment,
+/// e.g. `com.docker.backend` to `COM_DOCKER_BACKEND`.
+pub fn key_fragment(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_string()
+}
+
+/// Makes `keys` unique by suffixing repeats with their occurrence, e.g.
+/// `FOO_BAR` and `FOO_BAR_2` for `foo-bar` and `foo_bar`, since sketchybar
+/// silently keeps only the last value of a repeated key.
+pub fn unique_keys(keys: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    keys.into_iter()
+        .map(|key| {
+            let mut unique = key.clone();
+            let mut occurrence = 2;
+            while !seen.insert(unique.clone()) {
+                unique = format!("{key}_{occurrence}");
+                occurrence += 1;
+            }
+            unique
+        })
+        .collect()
+}
+
+/// Strips double quotes so `value` can be embedded in a quoted sketchybar value.
+pub fn strip_quotes(value: &str) -> String {
+    value.replace('"', "")
+}
+
 /// Returns the unit string, or an empty string when units are disabled.
 pub fn unit(no_units: bool, unit: &'static str) -> &'static str {
     if no_units { "" } else { unit }
@@ -22,6 +91,12 @@ pub fn unit(no_units: bool, unit: &'static str) -> &'static str {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_strip_quotes() {
+        assert_eq!(strip_quotes("say \"hi\""), "say hi");
+        assert_eq!(strip_quotes("Safari"), "Safari");
+    }
+
     #[test]
     fn test_unit_with_units() {
         assert_eq!(unit(false, "GB"), "GB");
@@ -35,4 +110,44 @@ mod tests {
         assert_eq!(unit(true, "%"), "");
         assert_eq!(unit(true, "KiB/s"), "");
     }
+
+    #[test]
+    fn test_key_fragment() {
+        assert_eq!(key_fragment("docker"), "DOCKER");
+        assert_eq!(key_fragment("/^postgres/"), "POSTGRES");
+        assert_eq!(key_fragment("com.docker.backend"), "COM_DOCKER_BACKEND");
+        assert_eq!(key_fragment("/System/Volumes/Data"), "SYSTEM_VOLUMES_DATA");
+        assert_eq!(key_fragment("/"), "");
+    }
+
+    #[test]
+    fn test_unique_keys() {
+        let keys = ["FOO_BAR", "BAZ", "FOO_BAR", "FOO_BAR_2", "FOO_BAR"];
+
+        assert_eq!(
+            unique_keys(keys.map(String::from)),
+            ["FOO_BAR", "BAZ", "FOO_BAR_2", "FOO_BAR_2_2", "FOO_BAR_3"]
+        );
+    }
+
+    #[test]
+    fn test_convert_temperature() {
+        assert_eq!(convert_temperature(40.0, TemperatureUnit::Celsius), 40.0);
+        assert_eq!(
+            convert_temperature(100.0, TemperatureUnit::Fahrenheit),
+            212.0
+        );
+        assert_eq!(
+            convert_temperature(-40.0, TemperatureUnit::Fahrenheit),
+            -40.0
+        );
+        assert_eq!(convert_temperature(0.0, TemperatureUnit::Kelvin), 273.15);
+    }
+
+    #[test]
+    fn test_temperature_suffix() {
+        assert_eq!(temperature_suffix(TemperatureUnit::Celsius), "°C");
+        assert_eq!(temperature_suffix(TemperatureUnit::Fahrenheit), "°F");
+        assert_eq!(temperature_suffix(TemperatureUnit::Kelvin), "K");
+    }
 }
@@ -1,4 +1,5 @@
-use super::{NO_TEMP_SENTINEL, unit};
+use super::{NO_TEMP_SENTINEL, convert_temperature, temperature_suffix, unit};
+use crate::cli::TemperatureUnit;
 use std::fmt::Write;
 use sysinfo::{Components, System};
 
@@ -6,6 +7,7 @@ pub fn get_cpu_stats(
     s: &System,
     components: &Components,
     flags: &[&str],
+    temperature_unit: TemperatureUnit,
     no_units: bool,
     buf: &mut String,
 ) {
@@ -49,9 +51,10 @@ pub fn get_cpu_stats(
                     NO_TEMP_SENTINEL
                 };
 
-                let unit = unit(no_units, "°C");
+                let unit = unit(no_units, temperature_suffix(temperature_unit));
                 if average_temp != NO_TEMP_SENTINEL {
-                    let _ = write!(buf, "CPU_TEMP=\"{average_temp:.1}{unit}\" ");
+                    let temp = convert_temperature(average_temp, temperature_unit);
+                    let _ = write!(buf, "CPU_TEMP=\"{temp:.1}{unit}\" ");
                 } else {
                     let _ = write!(buf, "CPU_TEMP=\"N/A{unit}\" ");
                 }
@@ -82,7 +85,14 @@ mod tests {
         let components = Components::new_with_refreshed_list();
         let mut buf = String::new();
 
-        get_cpu_stats(&s, &components, cli::ALL_CPU_FLAGS, false, &mut buf);
+        get_cpu_stats(
+            &s,
+            &components,
+            cli::ALL_CPU_FLAGS,
+            TemperatureUnit::Celsius,
+            false,
+            &mut buf,
+        );
 
         assert!(buf.contains("CPU_COUNT="));
         assert!(buf.contains("CPU_FREQUENCY="));
@@ -91,7 +101,14 @@ mod tests {
         assert!(buf.contains("%"));
 
         let mut no_units_buf = String::new();
-        get_cpu_stats(&s, &components, cli::ALL_CPU_FLAGS, true, &mut no_units_buf);
+        get_cpu_stats(
+            &s,
+            &components,
+            cli::ALL_CPU_FLAGS,
+            TemperatureUnit::Celsius,
+            true,
+            &mut no_units_buf,
+        );
 
         assert!(no_units_buf.contains("CPU_USAGE="));
         assert!(!no_units_buf.contains("%"));
@@ -99,6 +116,42 @@ mod tests {
         assert!(!no_units_buf.contains("°C"));
     }
 
+    #[test]
+    fn test_get_cpu_stats_temperature_unit_suffix() {
+        let mut s = System::new_all();
+        s.refresh_all();
+        let components = Components::new_with_refreshed_list();
+
+        for (temperature_unit, suffix) in [
+            (TemperatureUnit::Fahrenheit, "°F\""),
+            (TemperatureUnit::Kelvin, "K\""),
+        ] {
+            let mut buf = String::new();
+            get_cpu_stats(
+                &s,
+                &components,
+                &["temperature"],
+                temperature_unit,
+                false,
+                &mut buf,
+            );
+            assert!(buf.starts_with("CPU_TEMP=\""), "unexpected output: {buf}");
+            assert!(buf.contains(suffix), "missing {suffix} in: {buf}");
+            assert!(!buf.contains("°C"));
+
+            let mut no_units_buf = String::new();
+            get_cpu_stats(
+                &s,
+                &components,
+                &["temperature"],
+                temperature_unit,
+                true,
+                &mut no_units_buf,
+            );
+            assert!(!no_units_buf.contains(suffix));
+        }
+    }
+
     #[test]
     fn test_get_cpu_stats_empty_flags() {
         let mut s = System::new_all();
@@ -106,7 +159,14 @@ mod tests {
         let components = Components::new_with_refreshed_list();
         let mut buf = String::new();
 
-        get_cpu_stats(&s, &components, &[], false, &mut buf);
+        get_cpu_stats(
+            &s,
+            &components,
+            &[],
+            TemperatureUnit::Celsius,
+            false,
+            &mut buf,
+        );
 
         assert_eq!(buf, "");
     }
@@ -118,7 +178,14 @@ mod tests {
         let components = Components::new_with_refreshed_list();
         let mut buf = String::new();
 
-        get_cpu_stats(&s, &components, &["invalid_flag"], false, &mut buf);
+        get_cpu_stats(
+            &s,
+            &components,
+            &["invalid_flag"],
+            TemperatureUnit::Celsius,
+            false,
+            &mut buf,
+        );
 
         assert_eq!(buf, "");
     }
@@ -1,54 +1,246 @@
-use super::{BYTES_PER_GB, PERCENT, unit};
+use super::rates::counter_rates;
+use super::{FormatOptions, PERCENT, key_fragment, unique_keys, unit};
+use crate::cli::RemovableFilter;
+use std::collections::HashMap;
 use std::fmt::Write;
-use sysinfo::Disks;
+use std::time::Instant;
+use sysinfo::{Disk, Disks};
 
-pub fn get_disk_stats(disks: &Disks, flags: &[&str], no_units: bool, buf: &mut String) {
-    let (total_space, used_space) = disks.list().iter().fold((0, 0), |(total, used), disk| {
-        (
-            total + disk.total_space(),
-            used + disk.total_space() - disk.available_space(),
+/// Per-disk I/O counters used to compute throughput between ticks.
+struct DiskIoBaseline {
+    read_total: u64,
+    written_total: u64,
+    at: Instant,
+}
+
+/// Collector-owned I/O baselines keyed by mount point.
+#[derive(Default)]
+pub struct DiskRateBaselines {
+    by_mount: HashMap<String, DiskIoBaseline>,
+}
+
+impl DiskRateBaselines {
+    /// Returns the read/write rates in bytes per second since the previous tick and
+    /// resets the baseline for `mount_point` to the current cumulative totals.
+    fn update(&mut self, mount_point: &str, read_total: u64, written_total: u64) -> (f64, f64) {
+        let rates = match self.by_mount.get(mount_point) {
+            Some(baseline) => counter_rates(
+                Some(baseline.read_total),
+                Some(baseline.written_total),
+                read_total,
+                written_total,
+                baseline.at.elapsed().as_secs_f64(),
+            ),
+            None => (0.0, 0.0),
+        };
+
+        self.by_mount.insert(
+            mount_point.to_owned(),
+            DiskIoBaseline {
+                read_total,
+                written_total,
+                at: Instant::now(),
+            },
+        );
+
+        rates
+    }
+
+    /// Retains only baselines for disks that are currently selected.
+    fn retain_active(&mut self, active_mounts: &[String]) {
+        self.by_mount
+            .retain(|mount_point, _| active_mounts.contains(mount_point));
+    }
+}
+
+/// Selects which disks are reported and folded into the aggregate disk stats.
+///
+/// Empty include lists select everything; excludes are applied after includes.
+#[derive(Debug, Default)]
+pub struct DiskFilter {
+    pub include_mounts: Vec<String>,
+    pub exclude_mounts: Vec<String>,
+    pub include_file_systems: Vec<String>,
+    pub exclude_file_systems: Vec<String>,
+    pub removable: RemovableFilter,
+}
+
+impl DiskFilter {
+    fn selects(&self, mount_point: &str, file_system: &str, is_removable: bool) -> bool {
+        let file_system_in =
+            |list: &[String]| list.iter().any(|fs| fs.eq_ignore_ascii_case(file_system));
+        let mount_in = |list: &[String]| list.iter().any(|mount| mount == mount_point);
+
+        if !self.include_mounts.is_empty() && !mount_in(&self.include_mounts) {
+            return false;
+        }
+        if !self.include_file_systems.is_empty() && !file_system_in(&self.include_file_systems) {
+            return false;
+        }
+        if mount_in(&self.exclude_mounts) || file_system_in(&self.exclude_file_systems) {
+            return false;
+        }
+
+        match self.removable {
+            RemovableFilter::Include => true,
+            RemovableFilter::Exclude => !is_removable,
+            RemovableFilter::Only => is_removable,
+        }
+    }
+
+    fn selects_disk(&self, disk: &Disk) -> bool {
+        self.selects(
+            &disk.mount_point().to_string_lossy(),
+            &disk.file_system().to_string_lossy(),
+            disk.is_removable(),
         )
-    });
-    let disk_usage_percentage = if total_space > 0 {
-        ((used_space as f32 / total_space as f32) * PERCENT).round() as u32
-    } else {
-        0
-    };
+    }
+}
+
+/// Returns the key prefix for a single disk, e.g. `DISK__ROOT` for `/` and
+/// `DISK_SYSTEM_VOLUMES_DATA` for `/System/Volumes/Data`.
+///
+/// Key fragments never start with an underscore, so the root prefix cannot
+/// collide with a mount point such as `/root`.
+fn disk_key_prefix(mount_point: &str) -> String {
+    match key_fragment(mount_point).as_str() {
+        "" => "DISK__ROOT".to_string(),
+        fragment => format!("DISK_{fragment}"),
+    }
+}
+
+/// Capacity and throughput of one disk, or of every selected disk combined.
+#[derive(Default)]
+struct DiskSnapshot {
+    total_space: u64,
+    used_space: u64,
+    read_rate: f64,
+    write_rate: f64,
+}
+
+/// Writes a per-disk flag (everything but `count`) under `prefix`.
+fn write_disk_stat(
+    buf: &mut String,
+    prefix: &str,
+    flag: &str,
+    snapshot: &DiskSnapshot,
+    format: &FormatOptions,
+) {
+    let DiskSnapshot {
+        total_space,
+        used_space,
+        read_rate,
+        write_rate,
+    } = *snapshot;
+
+    match flag {
+        "free" => {
+            let _ = write!(
+                buf,
+                "{prefix}_FREE=\"{}\" ",
+                format.size(total_space - used_space)
+            );
+        }
+        "total" => {
+            let _ = write!(buf, "{prefix}_TOTAL=\"{}\" ", format.size(total_space));
+        }
+        "used" => {
+            let _ = write!(buf, "{prefix}_USED=\"{}\" ", format.size(used_space));
+        }
+        "usage" => {
+            let usage_percentage = if total_space > 0 {
+                ((used_space as f32 / total_space as f32) * PERCENT).round() as u32
+            } else {
+                0
+            };
+            let unit = unit(format.no_units, "%");
+            let _ = write!(buf, "{prefix}_USAGE=\"{usage_percentage}{unit}\" ");
+        }
+        "read_rate" => {
+            let _ = write!(buf, "{prefix}_READ_RATE=\"{}\" ", format.rate(read_rate));
+        }
+        "write_rate" => {
+            let _ = write!(buf, "{prefix}_WRITE_RATE=\"{}\" ", format.rate(write_rate));
+        }
+        _ => {}
+    }
+}
+
+pub fn get_disk_stats(
+    disks: &Disks,
+    flags: &[&str],
+    filter: &DiskFilter,
+    per_disk: bool,
+    baselines: &mut DiskRateBaselines,
+    format: &FormatOptions,
+    buf: &mut String,
+) {
+    let rate_flag_present = flags
+        .iter()
+        .any(|&flag| flag == "read_rate" || flag == "write_rate");
+
+    let selected: Vec<(String, DiskSnapshot)> = disks
+        .list()
+        .iter()
+        .filter(|disk| filter.selects_disk(disk))
+        .map(|disk| {
+            let mount_point = disk.mount_point().to_string_lossy().into_owned();
+            let (read_rate, write_rate) = if rate_flag_present {
+                let usage = disk.usage();
+                baselines.update(
+                    &mount_point,
+                    usage.total_read_bytes,
+                    usage.total_written_bytes,
+                )
+            } else {
+                (0.0, 0.0)
+            };
+            let snapshot = DiskSnapshot {
+                total_space: disk.total_space(),
+                used_space: disk.total_space() - disk.available_space(),
+                read_rate,
+                write_rate,
+            };
+            (mount_point, snapshot)
+        })
+        .collect();
+
+    if rate_flag_present {
+        let active_mounts: Vec<String> = selected
+            .iter()
+            .map(|(mount_point, _)| mount_point.clone())
+            .collect();
+        baselines.retain_active(&active_mounts);
+    }
+
+    let aggregate = selected
+        .iter()
+        .fold(DiskSnapshot::default(), |total, (_, disk)| DiskSnapshot {
+            total_space: total.total_space + disk.total_space,
+            used_space: total.used_space + disk.used_space,
+            read_rate: total.read_rate + disk.read_rate,
+            write_rate: total.write_rate + disk.write_rate,
+        });
 
     for &flag in flags {
         match flag {
             "count" => {
-                let _ = write!(buf, "DISK_COUNT=\"{}\" ", disks.list().len());
-            }
-            "free" => {
-                let unit = unit(no_units, "GB");
-                let _ = write!(
-                    buf,
-                    "DISK_FREE=\"{:.1}{unit}\" ",
-                    (total_space as f32 - used_space as f32) / BYTES_PER_GB
-                );
-            }
-            "total" => {
-                let unit = unit(no_units, "GB");
-                let _ = write!(
-                    buf,
-                    "DISK_TOTAL=\"{:.1}{unit}\" ",
-                    total_space as f32 / BYTES_PER_GB
-                );
-            }
-            "used" => {
-                let unit = unit(no_units, "GB");
-                let _ = write!(
-                    buf,
-                    "DISK_USED=\"{:.1}{unit}\" ",
-                    used_space as f32 / BYTES_PER_GB
-                );
+                let _ = write!(buf, "DISK_COUNT=\"{}\" ", selected.len());
             }
-            "usage" => {
-                let unit = unit(no_units, "%");
-                let _ = write!(buf, "DISK_USAGE=\"{disk_usage_percentage}{unit}\" ");
+            _ => write_disk_stat(buf, "DISK", flag, &aggregate, format),
+        }
+    }
+
+    if per_disk {
+        let prefixes = unique_keys(
+            selected
+                .iter()
+                .map(|(mount_point, _)| disk_key_prefix(mount_point)),
+        );
+        for ((_, snapshot), prefix) in selected.iter().zip(&prefixes) {
+            for &flag in flags {
+                write_disk_stat(buf, prefix, flag, snapshot, format);
             }
-            _ => {}
         }
     }
 }
@@ -64,13 +256,23 @@ mod tests {
         let disks = Disks::new_with_refreshed_list();
         let mut buf = String::new();
 
-        get_disk_stats(&disks, cli::ALL_DISK_FLAGS, false, &mut buf);
+        get_disk_stats(
+            &disks,
+            cli::ALL_DISK_FLAGS,
+            &DiskFilter::default(),
+            false,
+            &mut DiskRateBaselines::default(),
+            &FormatOptions::default(),
+            &mut buf,
+        );
 
         assert!(buf.contains("DISK_COUNT="));
         assert!(buf.contains("DISK_FREE="));
         assert!(buf.contains("DISK_TOTAL="));
         assert!(buf.contains("DISK_USED="));
         assert!(buf.contains("DISK_USAGE="));
+        assert!(buf.contains("DISK_READ_RATE="));
+        assert!(buf.contains("DISK_WRITE_RATE="));
     }
 
     #[test]
@@ -78,7 +280,15 @@ mod tests {
         let disks = Disks::new_with_refreshed_list();
         let mut buf = String::new();
 
-        get_disk_stats(&disks, &["bogus"], false, &mut buf);
+        get_disk_stats(
+            &disks,
+            &["bogus"],
+            &DiskFilter::default(),
+            true,
+            &mut DiskRateBaselines::default(),
+            &FormatOptions::default(),
+            &mut buf,
+        );
 
         assert_eq!(buf, "");
     }
@@ -88,7 +298,18 @@ mod tests {
         let disks = Disks::new_with_refreshed_list();
         let mut buf = String::new();
 
-        get_disk_stats(&disks, &["total"], true, &mut buf);
+        get_disk_stats(
+            &disks,
+            &["total"],
+            &DiskFilter::default(),
+            false,
+            &mut DiskRateBaselines::default(),
+            &FormatOptions {
+                no_units: true,
+                ..FormatOptions::default()
+            },
+            &mut buf,
+        );
 
         if !buf.is_empty() {
             assert!(!buf.contains("GB"));
@@ -100,8 +321,217 @@ mod tests {
         let disks = Disks::new_with_refreshed_list();
         let mut buf = String::new();
 
-        get_disk_stats(&disks, &[], false, &mut buf);
+        get_disk_stats(
+            &disks,
+            &[],
+            &DiskFilter::default(),
+            false,
+            &mut DiskRateBaselines::default(),
+            &FormatOptions::default(),
+            &mut buf,
+        );
 
         assert_eq!(buf, "");
     }
+
+    #[test]
+    fn test_get_disk_stats_per_disk_keys() {
+        let disks = Disks::new_with_refreshed_list();
+        let mut buf = String::new();
+
+        get_disk_stats(
+            &disks,
+            &["count", "used"],
+            &DiskFilter::default(),
+            true,
+            &mut DiskRateBaselines::default(),
+            &FormatOptions::default(),
+            &mut buf,
+        );
+
+        let prefixes = unique_keys(
+            disks
+                .list()
+                .iter()
+                .map(|disk| disk_key_prefix(&disk.mount_point().to_string_lossy())),
+        );
+        for prefix in prefixes {
+            assert!(
+                buf.contains(&format!("{prefix}_USED=")),
+                "missing {prefix}_USED in: {buf}"
+            );
+        }
+        assert_eq!(buf.matches("_COUNT=").count(), 1, "count is aggregate-only");
+    }
+
+    #[test]
+    fn test_get_disk_stats_excluded_disks_are_not_counted() {
+        let disks = Disks::new_with_refreshed_list();
+        let filter = DiskFilter {
+            include_mounts: vec!["/definitely/not/a/mount".to_string()],
+            ..DiskFilter::default()
+        };
+        let mut buf = String::new();
+
+        get_disk_stats(
+            &disks,
+            &["count", "total"],
+            &filter,
+            true,
+            &mut DiskRateBaselines::default(),
+            &FormatOptions {
+                no_units: true,
+                ..FormatOptions::default()
+            },
+            &mut buf,
+        );
+
+        assert_eq!(buf, "DISK_COUNT=\"0\" DISK_TOTAL=\"0.0\" ");
+    }
+
+    #[test]
+    fn test_get_disk_stats_rates_start_at_zero_and_track_baselines() {
+        let disks = Disks::new_with_refreshed_list();
+        let mut baselines = DiskRateBaselines::default();
+        let mut buf = String::new();
+
+        get_disk_stats(
+            &disks,
+            &["read_rate", "write_rate"],
+            &DiskFilter::default(),
+            false,
+            &mut baselines,
+            &FormatOptions {
+                no_units: true,
+                ..FormatOptions::default()
+            },
+            &mut buf,
+        );
+
+        assert_eq!(buf, "DISK_READ_RATE=\"0\" DISK_WRITE_RATE=\"0\" ");
+        assert_eq!(baselines.by_mount.len(), disks.list().len());
+    }
+
+    #[test]
+    fn test_get_disk_stats_capacity_flags_skip_baselines() {
+        let disks = Disks::new_with_refreshed_list();
+        let mut baselines = DiskRateBaselines::default();
+        let mut buf = String::new();
+
+        get_disk_stats(
+            &disks,
+            &["used"],
+            &DiskFilter::default(),
+            true,
+            &mut baselines,
+            &FormatOptions::default(),
+            &mut buf,
+        );
+
+        assert!(baselines.by_mount.is_empty());
+    }
+
+    #[test]
+    fn test_disk_rate_baselines_update() {
+        let mut baselines = DiskRateBaselines::default();
+
+        assert_eq!(baselines.update("/", 4096, 8192), (0.0, 0.0));
+
+        baselines.by_mount.get_mut("/").unwrap().at -= std::time::Duration::from_secs(1);
+        let (read_rate, write_rate) = baselines.update("/", 4096 + 10_240, 8192 + 20_480);
+        assert!(
+            (9_000.0..=10_240.0).contains(&read_rate),
+            "read rate {read_rate}"
+        );
+        assert!(
+            (18_000.0..=20_480.0).contains(&write_rate),
+            "write rate {write_rate}"
+        );
+    }
+
+    #[test]
+    fn test_disk_rate_baselines_counter_reset_returns_zero() {
+        let mut baselines = DiskRateBaselines::default();
+        baselines.update("/", 100_000, 100_000);
+
+        assert_eq!(baselines.update("/", 10, 200_000), (0.0, 0.0));
+        assert_eq!(baselines.by_mount["/"].read_total, 10);
+    }
+
+    #[test]
+    fn test_disk_rate_baselines_retain_active() {
+        let mut baselines = DiskRateBaselines::default();
+        baselines.update("/", 1, 1);
+        baselines.update("/Volumes/USB", 1, 1);
+
+        baselines.retain_active(&["/".to_string()]);
+
+        assert!(baselines.by_mount.contains_key("/"));
+        assert!(!baselines.by_mount.contains_key("/Volumes/USB"));
+    }
+
+    #[test]
+    fn test_disk_key_prefix() {
+        assert_eq!(disk_key_prefix("/"), "DISK__ROOT");
+        assert_eq!(disk_key_prefix("/root"), "DISK_ROOT");
+        assert_eq!(
+            disk_key_prefix("/System/Volumes/Data"),
+            "DISK_SYSTEM_VOLUMES_DATA"
+        );
+        assert_eq!(
+            disk_key_prefix("/Volumes/My Drive"),
+            "DISK_VOLUMES_MY_DRIVE"
+        );
+    }
+
+    #[test]
+    fn test_disk_filter_default_selects_everything() {
+        let filter = DiskFilter::default();
+
+        assert!(filter.selects("/", "apfs", false));
+        assert!(filter.selects("/Volumes/USB", "exfat", true));
+    }
+
+    #[test]
+    fn test_disk_filter_mounts() {
+        let filter = DiskFilter {
+            include_mounts: vec!["/".to_string(), "/home".to_string()],
+            exclude_mounts: vec!["/home".to_string()],
+            ..DiskFilter::default()
+        };
+
+        assert!(filter.selects("/", "ext4", false));
+        assert!(!filter.selects("/home", "ext4", false));
+        assert!(!filter.selects("/boot", "ext4", false));
+    }
+
+    #[test]
+    fn test_disk_filter_file_systems_ignore_case() {
+        let filter = DiskFilter {
+            include_file_systems: vec!["APFS".to_string(), "ext4".to_string()],
+            exclude_file_systems: vec!["EXT4".to_string()],
+            ..DiskFilter::default()
+        };
+
+        assert!(filter.selects("/", "apfs", false));
+        assert!(!filter.selects("/data", "ext4", false));
+        assert!(!filter.selects("/boot", "vfat", false));
+    }
+
+    #[test]
+    fn test_disk_filter_removable() {
+        let exclude = DiskFilter {
+            removable: RemovableFilter::Exclude,
+            ..DiskFilter::default()
+        };
+        assert!(exclude.selects("/", "apfs", false));
+        assert!(!exclude.selects("/Volumes/USB", "exfat", true));
+
+        let only = DiskFilter {
+            removable: RemovableFilter::Only,
+            ..DiskFilter::default()
+        };
+        assert!(!only.selects("/", "apfs", false));
+        assert!(only.selects("/Volumes/USB", "exfat", true));
+    }
 }
@@ -0,0 +1,37 @@
+//! External power state from the IOKit power source API on macOS.
+
+use std::os::raw::c_int;
+
+#[link(name = "power_source", kind = "static")]
+unsafe extern "C" {
+    fn providing_power_source() -> c_int;
+    fn external_adapter_watts() -> c_int;
+}
+
+/// The power source currently powering the machine, as reported by
+/// `IOPSGetProvidingPowerSourceType`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum ProvidingSource {
+    Ac,
+    Battery,
+    Ups,
+}
+
+/// Returns the power source powering the machine, or `None` when IOKit does
+/// not report one.
+pub(super) fn providing_source() -> Option<ProvidingSource> {
+    // SAFETY: takes no arguments and releases the snapshot it copies.
+    match unsafe { providing_power_source() } {
+        0 => Some(ProvidingSource::Ac),
+        1 => Some(ProvidingSource::Battery),
+        2 => Some(ProvidingSource::Ups),
+        _ => None,
+    }
+}
+
+/// Returns the maximum power of the plugged in adapter in watts, as reported
+/// by `IOPSCopyExternalPowerAdapterDetails`.
+pub(super) fn adapter_watts() -> Option<u32> {
+    // SAFETY: takes no arguments and releases the details it copies.
+    u32::try_from(unsafe { external_adapter_watts() }).ok()
+}
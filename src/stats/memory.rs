@@ -1,11 +1,177 @@
-use super::{BYTES_PER_GB, PERCENT, unit};
+use super::rates::counter_rates;
+use super::{BYTES_PER_KB, FormatOptions, PERCENT, unit};
 use crate::cli;
 use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
 use sysinfo::System;
 
-pub fn get_memory_stats(s: &System, flags: &[&str], no_units: bool, buf: &mut String) {
+/// RAM flags read from `/proc/meminfo`.
+const MEMINFO_FLAGS: &[&str] = &[
+    "ram_buffers",
+    "ram_cached",
+    "ram_dirty",
+    "ram_free",
+    "ram_shared",
+    "ram_slab",
+];
+
+/// Swap flags computed from the `/proc/vmstat` paging counters.
+const SWAP_RATE_FLAGS: &[&str] = &["swp_in_rate", "swp_out_rate"];
+
+/// Cumulative number of pages swapped in and out, as counted by the kernel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct SwapCounters {
+    pages_in: u64,
+    pages_out: u64,
+}
+
+/// Swap counters used to compute paging rates between ticks.
+struct SwapBaseline {
+    counters: SwapCounters,
+    at: Instant,
+}
+
+/// Collector-owned swap counters of the previous tick.
+#[derive(Default)]
+pub struct SwapRateBaselines {
+    previous: Option<SwapBaseline>,
+}
+
+impl SwapRateBaselines {
+    /// Returns the swap-in/swap-out rates in pages per second since the
+    /// previous tick and resets the baseline to `counters`.
+    fn update(&mut self, counters: SwapCounters) -> (f64, f64) {
+        let rates = match &self.previous {
+            Some(baseline) => counter_rates(
+                Some(baseline.counters.pages_in),
+                Some(baseline.counters.pages_out),
+                counters.pages_in,
+                counters.pages_out,
+                baseline.at.elapsed().as_secs_f64(),
+            ),
+            None => (0.0, 0.0),
+        };
+
+        self.previous = Some(SwapBaseline {
+            counters,
+            at: Instant::now(),
+        });
+        rates
+    }
+}
+
+/// Parses the swap counters out of the contents of `/proc/vmstat`.
+fn parse_vmstat(contents: &str) -> Option<SwapCounters> {
+    let (mut pages_in, mut pages_out) = (None, None);
+    for line in contents.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        match key {
+            "pswpin" => pages_in = value.trim().parse().ok(),
+            "pswpout" => pages_out = value.trim().parse().ok(),
+            _ => {}
+        }
+    }
+    Some(SwapCounters {
+        pages_in: pages_in?,
+        pages_out: pages_out?,
+    })
+}
+
+/// Reads the swap counters from `vmstat` under `proc_root` on Linux.
+fn read_vmstat(proc_root: &Path) -> Option<SwapCounters> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    fs::read_to_string(proc_root.join("vmstat"))
+        .ok()
+        .and_then(|contents| parse_vmstat(&contents))
+}
+
+/// RAM breakdown from `/proc/meminfo`, in bytes. Fields the kernel does not
+/// report are `None`.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct MemInfo {
+    free: Option<u64>,
+    buffers: Option<u64>,
+    cached: Option<u64>,
+    shared: Option<u64>,
+    dirty: Option<u64>,
+    slab: Option<u64>,
+}
+
+/// Parses the contents of `/proc/meminfo`.
+fn parse_meminfo(contents: &str) -> MemInfo {
+    let mut meminfo = MemInfo::default();
+    for line in contents.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let mut parts = value.split_whitespace();
+        let Some(value) = parts.next().and_then(|value| value.parse::<u64>().ok()) else {
+            continue;
+        };
+        let bytes = match parts.next() {
+            Some("kB") => value * BYTES_PER_KB,
+            _ => value,
+        };
+
+        let field = match key {
+            "MemFree" => &mut meminfo.free,
+            "Buffers" => &mut meminfo.buffers,
+            "Cached" => &mut meminfo.cached,
+            "Shmem" => &mut meminfo.shared,
+            "Dirty" => &mut meminfo.dirty,
+            "Slab" => &mut meminfo.slab,
+            _ => continue,
+        };
+        *field = Some(bytes);
+    }
+    meminfo
+}
+
+/// Reads `meminfo` under `proc_root` on Linux.
+fn read_meminfo(proc_root: &Path) -> Option<MemInfo> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    fs::read_to_string(proc_root.join("meminfo"))
+        .ok()
+        .map(|contents| parse_meminfo(&contents))
+}
+
+pub fn get_memory_stats(
+    s: &System,
+    flags: &[&str],
+    baselines: &mut SwapRateBaselines,
+    format: &FormatOptions,
+    proc_root: &Path,
+    buf: &mut String,
+) {
     let ram_flag_present = flags.iter().any(|&flag| cli::ALL_RAM_FLAGS.contains(&flag));
     let swp_flag_present = flags.iter().any(|&flag| cli::ALL_SWP_FLAGS.contains(&flag));
+    let meminfo = if flags.iter().any(|&flag| MEMINFO_FLAGS.contains(&flag)) {
+        read_meminfo(proc_root)
+    } else {
+        None
+    };
+    // Without `/proc/meminfo` (elsewhere than Linux) the keys are omitted.
+    let write_meminfo = |buf: &mut String, key: &str, field: fn(&MemInfo) -> Option<u64>| {
+        let Some(meminfo) = &meminfo else {
+            return;
+        };
+        match field(meminfo) {
+            Some(bytes) => {
+                let _ = write!(buf, "{key}=\"{}\" ", format.size(bytes));
+            }
+            None => {
+                let _ = write!(buf, "{key}=\"N/A\" ");
+            }
+        }
+    };
 
     let (ram_total, ram_used, ram_usage_percentage) = if ram_flag_present {
         let ram_total = s.total_memory();
@@ -19,7 +185,7 @@ pub fn get_memory_stats(s: &System, flags: &[&str], no_units: bool, buf: &mut St
     } else {
         (0, 0, 0)
     };
-    let (swp_total, swp_used, swp_usage_percentage) = if swp_flag_present {
+    let (swp_total, swp_used, swp_usage_percentage, swp_rates) = if swp_flag_present {
         let swp_total = s.total_swap();
         let swp_used = s.used_swap();
         let swp_usage_percentage = if swp_total > 0 {
@@ -27,67 +193,78 @@ pub fn get_memory_stats(s: &System, flags: &[&str], no_units: bool, buf: &mut St
         } else {
             0
         };
-        (swp_total, swp_used, swp_usage_percentage)
+        let swp_rates = if flags.iter().any(|&flag| SWAP_RATE_FLAGS.contains(&flag)) {
+            read_vmstat(proc_root).map(|counters| baselines.update(counters))
+        } else {
+            None
+        };
+        (swp_total, swp_used, swp_usage_percentage, swp_rates)
     } else {
-        (0, 0, 0)
+        (0, 0, 0, None)
+    };
+    let write_swap_rate = |buf: &mut String, key: &str, rate: Option<f64>| match rate {
+        Some(rate) => {
+            let unit = unit(format.no_units, "pages/s");
+            let _ = write!(buf, "{key}=\"{rate:.0}{unit}\" ");
+        }
+        None => {
+            let _ = write!(buf, "{key}=\"N/A\" ");
+        }
     };
 
     for &flag in flags {
         match flag {
             "ram_available" => {
-                let unit = unit(no_units, "GB");
                 let _ = write!(
                     buf,
-                    "RAM_AVAILABLE=\"{:.1}{unit}\" ",
-                    s.available_memory() as f32 / BYTES_PER_GB
+                    "RAM_AVAILABLE=\"{}\" ",
+                    format.size(s.available_memory())
                 );
             }
+            "ram_buffers" => write_meminfo(buf, "RAM_BUFFERS", |meminfo| meminfo.buffers),
+            "ram_cached" => write_meminfo(buf, "RAM_CACHED", |meminfo| meminfo.cached),
+            "ram_dirty" => write_meminfo(buf, "RAM_DIRTY", |meminfo| meminfo.dirty),
+            "ram_free" => {
+                // Elsewhere than Linux, fall back to the free memory sysinfo reports.
+                let free = meminfo
+                    .as_ref()
+                    .and_then(|meminfo| meminfo.free)
+                    .unwrap_or_else(|| s.free_memory());
+                let _ = write!(buf, "RAM_FREE=\"{}\" ", format.size(free));
+            }
+            "ram_shared" => write_meminfo(buf, "RAM_SHARED", |meminfo| meminfo.shared),
+            "ram_slab" => write_meminfo(buf, "RAM_SLAB", |meminfo| meminfo.slab),
             "ram_total" => {
-                let unit = unit(no_units, "GB");
-                let _ = write!(
-                    buf,
-                    "RAM_TOTAL=\"{:.1}{unit}\" ",
-                    ram_total as f32 / BYTES_PER_GB
-                );
+                let _ = write!(buf, "RAM_TOTAL=\"{}\" ", format.size(ram_total));
             }
             "ram_used" => {
-                let unit = unit(no_units, "GB");
-                let _ = write!(
-                    buf,
-                    "RAM_USED=\"{:.1}{unit}\" ",
-                    ram_used as f32 / BYTES_PER_GB
-                );
+                let _ = write!(buf, "RAM_USED=\"{}\" ", format.size(ram_used));
             }
             "ram_usage" => {
-                let unit = unit(no_units, "%");
+                let unit = unit(format.no_units, "%");
                 let _ = write!(buf, "RAM_USAGE=\"{ram_usage_percentage}{unit}\" ");
             }
-            "swp_free" => {
-                let unit = unit(no_units, "GB");
-                let _ = write!(
+            "swp_in_rate" => {
+                write_swap_rate(buf, "SWP_IN_RATE", swp_rates.map(|(pages_in, _)| pages_in));
+            }
+            "swp_out_rate" => {
+                write_swap_rate(
                     buf,
-                    "SWP_FREE=\"{:.1}{unit}\" ",
-                    s.free_swap() as f32 / BYTES_PER_GB
+                    "SWP_OUT_RATE",
+                    swp_rates.map(|(_, pages_out)| pages_out),
                 );
             }
+            "swp_free" => {
+                let _ = write!(buf, "SWP_FREE=\"{}\" ", format.size(s.free_swap()));
+            }
             "swp_total" => {
-                let unit = unit(no_units, "GB");
-                let _ = write!(
-                    buf,
-                    "SWP_TOTAL=\"{:.1}{unit}\" ",
-                    swp_total as f32 / BYTES_PER_GB
-                );
+                let _ = write!(buf, "SWP_TOTAL=\"{}\" ", format.size(swp_total));
             }
             "swp_used" => {
-                let unit = unit(no_units, "GB");
-                let _ = write!(
-                    buf,
-                    "SWP_USED=\"{:.1}{unit}\" ",
-                    swp_used as f32 / BYTES_PER_GB
-                );
+                let _ = write!(buf, "SWP_USED=\"{}\" ", format.size(swp_used));
             }
             "swp_usage" => {
-                let unit = unit(no_units, "%");
+                let unit = unit(format.no_units, "%");
                 let _ = write!(buf, "SWP_USAGE=\"{swp_usage_percentage}{unit}\" ");
             }
             _ => {}
@@ -101,20 +278,85 @@ mod tests {
 
     #[test]
     fn test_get_memory_stats_all_flags_emit_expected_keys() {
+        let proc_root =
+            std::env::temp_dir().join(format!("stats_provider_memory_all_{}", std::process::id()));
+        fs::create_dir_all(&proc_root).unwrap();
+        fs::write(proc_root.join("meminfo"), MEMINFO).unwrap();
         let mut s = System::new_all();
         s.refresh_all();
         let mut buf = String::new();
 
-        get_memory_stats(&s, cli::ALL_MEMORY_FLAGS, false, &mut buf);
+        get_memory_stats(
+            &s,
+            cli::ALL_MEMORY_FLAGS,
+            &mut SwapRateBaselines::default(),
+            &FormatOptions::default(),
+            &proc_root,
+            &mut buf,
+        );
 
+        // `/proc/meminfo` is only read on Linux; elsewhere its keys are omitted.
+        for key in [
+            "RAM_BUFFERS=",
+            "RAM_CACHED=",
+            "RAM_DIRTY=",
+            "RAM_SHARED=",
+            "RAM_SLAB=",
+        ] {
+            assert_eq!(buf.contains(key), cfg!(target_os = "linux"), "{key}");
+        }
         assert!(buf.contains("RAM_AVAILABLE="));
+        assert!(buf.contains("RAM_FREE="));
         assert!(buf.contains("RAM_TOTAL="));
         assert!(buf.contains("RAM_USED="));
         assert!(buf.contains("RAM_USAGE="));
+        assert!(buf.contains("SWP_IN_RATE="));
+        assert!(buf.contains("SWP_OUT_RATE="));
         assert!(buf.contains("SWP_FREE="));
         assert!(buf.contains("SWP_TOTAL="));
         assert!(buf.contains("SWP_USED="));
         assert!(buf.contains("SWP_USAGE="));
+
+        fs::remove_dir_all(&proc_root).unwrap();
+    }
+
+    #[test]
+    fn test_get_memory_stats_unit_system_labels() {
+        use crate::cli::UnitSystem;
+
+        let mut s = System::new_all();
+        s.refresh_all();
+
+        let mut iec_buf = String::new();
+        let iec = FormatOptions {
+            unit_system: Some(UnitSystem::Iec),
+            ..FormatOptions::default()
+        };
+        get_memory_stats(
+            &s,
+            &["ram_total"],
+            &mut SwapRateBaselines::default(),
+            &iec,
+            Path::new(cli::DEFAULT_PROC_ROOT),
+            &mut iec_buf,
+        );
+        assert!(iec_buf.ends_with("GiB\" "), "unexpected output: {iec_buf}");
+
+        let mut si_buf = String::new();
+        let si = FormatOptions {
+            unit_system: Some(UnitSystem::Si),
+            ..FormatOptions::default()
+        };
+        get_memory_stats(
+            &s,
+            &["ram_total"],
+            &mut SwapRateBaselines::default(),
+            &si,
+            Path::new(cli::DEFAULT_PROC_ROOT),
+            &mut si_buf,
+        );
+        assert!(si_buf.ends_with("GB\" "), "unexpected output: {si_buf}");
+        assert!(!si_buf.contains("GiB"));
     }
 
     #[test]
@@ -122,8 +364,167 @@ mod tests {
         let s = System::new_all();
         let mut buf = String::new();
 
-        get_memory_stats(&s, &[], false, &mut buf);
+        get_memory_stats(
+            &s,
+            &[],
+            &mut SwapRateBaselines::default(),
+            &FormatOptions::default(),
+            Path::new(cli::DEFAULT_PROC_ROOT),
+            &mut buf,
+        );
+
+        assert_eq!(buf, "");
+    }
+
+    const MEMINFO: &str = "\
+MemTotal:       16303524 kB
+MemFree:         1048576 kB
+MemAvailable:    9437184 kB
+Buffers:          524288 kB
+Cached:          6291456 kB
+SwapCached:            0 kB
+Shmem:            262144 kB
+Dirty:               512 kB
+Slab:             786432 kB
+HugePages_Total:       0
+";
+
+    #[test]
+    fn test_parse_meminfo() {
+        assert_eq!(
+            parse_meminfo(MEMINFO),
+            MemInfo {
+                free: Some(1024 * 1024 * 1024),
+                buffers: Some(512 * 1024 * 1024),
+                cached: Some(6 * 1024 * 1024 * 1024),
+                shared: Some(256 * 1024 * 1024),
+                dirty: Some(512 * 1024),
+                slab: Some(768 * 1024 * 1024),
+            }
+        );
+        assert_eq!(
+            parse_meminfo("garbage\nMemFree: lots kB\n"),
+            MemInfo::default()
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_get_memory_stats_reads_meminfo_from_proc_root() {
+        let proc_root =
+            std::env::temp_dir().join(format!("stats_provider_meminfo_{}", std::process::id()));
+        fs::create_dir_all(&proc_root).unwrap();
+        fs::write(proc_root.join("meminfo"), MEMINFO).unwrap();
+        let s = System::new();
+        let mut buf = String::new();
+
+        get_memory_stats(
+            &s,
+            &[
+                "ram_free",
+                "ram_cached",
+                "ram_buffers",
+                "ram_shared",
+                "ram_slab",
+            ],
+            &mut SwapRateBaselines::default(),
+            &FormatOptions::default(),
+            &proc_root,
+            &mut buf,
+        );
+
+        assert_eq!(
+            buf,
+            "RAM_FREE=\"1.0GB\" RAM_CACHED=\"6.0GB\" RAM_BUFFERS=\"0.5GB\" \
+             RAM_SHARED=\"0.2GB\" RAM_SLAB=\"0.8GB\" "
+        );
 
+        let mut buf = String::new();
+        get_memory_stats(
+            &s,
+            &["ram_dirty"],
+            &mut SwapRateBaselines::default(),
+            &FormatOptions::default(),
+            Path::new("/nonexistent/proc"),
+            &mut buf,
+        );
         assert_eq!(buf, "");
+
+        fs::remove_dir_all(&proc_root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_vmstat() {
+        assert_eq!(
+            parse_vmstat("nr_free_pages 12345\npswpin 120\npswpout 4500\npgfault 9\n"),
+            Some(SwapCounters {
+                pages_in: 120,
+                pages_out: 4500,
+            })
+        );
+        assert_eq!(parse_vmstat("nr_free_pages 12345\npswpin 120\n"), None);
+    }
+
+    #[test]
+    fn test_swap_rate_baselines_update() {
+        let mut baselines = SwapRateBaselines::default();
+        let first = SwapCounters {
+            pages_in: 100,
+            pages_out: 200,
+        };
+
+        // The first tick has no baseline to compare against.
+        assert_eq!(baselines.update(first), (0.0, 0.0));
+
+        baselines.previous.as_mut().unwrap().at -= std::time::Duration::from_secs(2);
+        let (pages_in, pages_out) = baselines.update(SwapCounters {
+            pages_in: 140,
+            pages_out: 1200,
+        });
+        assert!((pages_in - 20.0).abs() < 0.5, "unexpected rate: {pages_in}");
+        assert!(
+            (pages_out - 500.0).abs() < 5.0,
+            "unexpected rate: {pages_out}"
+        );
+
+        // Counters that went backwards report no activity.
+        assert_eq!(baselines.update(first), (0.0, 0.0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_get_memory_stats_reads_vmstat_from_proc_root() {
+        let proc_root =
+            std::env::temp_dir().join(format!("stats_provider_vmstat_{}", std::process::id()));
+        fs::create_dir_all(&proc_root).unwrap();
+        fs::write(proc_root.join("vmstat"), "pswpin 10\npswpout 20\n").unwrap();
+        let s = System::new();
+        let mut baselines = SwapRateBaselines::default();
+        let mut buf = String::new();
+
+        get_memory_stats(
+            &s,
+            &["swp_in_rate", "swp_out_rate"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &proc_root,
+            &mut buf,
+        );
+
+        assert_eq!(buf, "SWP_IN_RATE=\"0pages/s\" SWP_OUT_RATE=\"0pages/s\" ");
+        assert!(baselines.previous.is_some());
+
+        let mut buf = String::new();
+        get_memory_stats(
+            &s,
+            &["swp_in_rate"],
+            &mut SwapRateBaselines::default(),
+            &FormatOptions::default(),
+            Path::new("/nonexistent/proc"),
+            &mut buf,
+        );
+        assert_eq!(buf, "SWP_IN_RATE=\"N/A\" ");
+
+        fs::remove_dir_all(&proc_root).unwrap();
     }
 }
@@ -2,25 +2,55 @@ mod battery;
 mod constants;
 mod cpu;
 mod disk;
+#[cfg(target_os = "macos")]
+mod iokit;
 mod memory;
 mod network;
+mod network_usage;
+mod power_supply;
+mod pressure;
+mod process;
+mod rates;
 mod system;
+mod units;
 mod uptime;
 
-use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, ProcessRefreshKind, RefreshKind};
 
-pub use battery::get_battery_stats;
+pub use battery::{
+    BATTERY_EVENTS, BatteryEvents, BatteryOptions, BatteryThresholds, get_battery_stats,
+};
 pub use constants::*;
 pub use cpu::get_cpu_stats;
-pub use disk::get_disk_stats;
-pub use memory::get_memory_stats;
-pub use network::NetworkRateBaselines;
+pub use disk::{DiskFilter, DiskRateBaselines, get_disk_stats};
+pub use memory::{SwapRateBaselines, get_memory_stats};
 pub use network::get_network_stats;
+pub use network::{InterfaceSpec, NetworkOptions, NetworkRateBaselines};
+pub use network_usage::NetworkUsage;
+pub use pressure::get_pressure_stats;
+pub use process::{ProcessWatch, get_process_stats, get_watched_process_stats};
 pub use system::get_system_stats;
+pub use units::FormatOptions;
 pub use uptime::get_uptime_stats;
 
-pub fn build_refresh_kind() -> RefreshKind {
-    RefreshKind::nothing()
+/// Builds the sysinfo refresh kind for each tick.
+///
+/// Processes are only enumerated when `processes` is set, since listing them is
+/// considerably more expensive than the CPU and memory refreshes. Their threads
+/// are only listed when `threads` is also set.
+pub fn build_refresh_kind(processes: bool, threads: bool) -> RefreshKind {
+    let refresh_kind = RefreshKind::nothing()
         .with_cpu(CpuRefreshKind::nothing().with_cpu_usage().with_frequency())
-        .with_memory(MemoryRefreshKind::nothing().with_ram().with_swap())
+        .with_memory(MemoryRefreshKind::nothing().with_ram().with_swap());
+
+    if processes {
+        let process_kind = ProcessRefreshKind::nothing().with_cpu().with_memory();
+        refresh_kind.with_processes(if threads {
+            process_kind.with_tasks()
+        } else {
+            process_kind
+        })
+    } else {
+        refresh_kind
+    }
 }
@@ -1,36 +1,385 @@
 use std::collections::HashMap;
 use std::fmt::Write;
+use std::fs;
+use std::net::IpAddr;
+use std::ops::AddAssign;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
 use std::time::Instant;
 
-use super::{BYTES_PER_KB, unit};
-use sysinfo::Networks;
+use super::network_usage::{InterfaceUsage, LocalDate, NetworkUsage};
+use super::rates::counter_rates;
+use super::{FormatOptions, PERCENT, unit};
+use crate::cli::{ALL_NETWORK_FLAGS, DEFAULT_PROC_ROOT, RateUnit};
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use sysinfo::{NetworkData, Networks};
+
+/// How a `--network` spec selects interfaces.
+#[derive(Debug)]
+enum InterfacePattern {
+    Name(String),
+    Glob(Regex),
+    /// `auto`: the interface carrying the default route.
+    Primary,
+    /// `total`: the sum of the other selected interfaces, or of all
+    /// interfaces not excluded from totals when no other is selected.
+    Total,
+}
+
+/// Interfaces left out of `total` unless `--network-total-exclude` is set:
+/// loopback, tunnels, bridges and other virtual interfaces.
+const DEFAULT_TOTAL_EXCLUDES: &[&str] = &[
+    "lo*", "utun*", "awdl*", "llw*", "bridge*", "gif*", "stf*", "anpi*", "ap1", "vmnet*", "veth*",
+    "docker*", "br-*", "virbr*", "tun*", "tap*",
+];
+
+/// `DEFAULT_TOTAL_EXCLUDES`, compiled once. Also keeps virtual interfaces
+/// from being picked by `auto`.
+static VIRTUAL_INTERFACES: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    DEFAULT_TOTAL_EXCLUDES
+        .iter()
+        .map(|glob| glob_regex(glob).expect("default total excludes are valid globs"))
+        .collect()
+});
+
+/// Per-interface address flags, which have no meaning for `total`.
+const ADDRESS_FLAGS: &[&str] = &["has_address", "ipv4", "ipv6", "mac"];
+
+/// Key suffix used for `total` unless it is aliased.
+const TOTAL_KEY: &str = "TOTAL";
+
+/// Key suffix used for the `auto` interface unless it is aliased.
+const PRIMARY_KEY: &str = "PRIMARY";
+
+/// `RTF_UP` in the flags column of `/proc/net/route`.
+const ROUTE_FLAG_UP: u32 = 0x1;
+
+/// Network settings that do not change between ticks.
+#[derive(Debug)]
+pub struct NetworkOptions {
+    pub rate_unit: RateUnit,
+    /// Root of the proc file system, read for the default route.
+    pub proc_root: PathBuf,
+    /// Globs of interfaces left out of `total`.
+    pub total_excludes: Vec<Regex>,
+    /// Monthly data quota in bytes, used for the `month_usage` percentage.
+    pub quota: Option<u64>,
+    /// Report interfaces named in `--network` that do not exist (yet) as down
+    /// instead of skipping them.
+    pub lenient: bool,
+}
+
+impl NetworkOptions {
+    /// Builds the options, using the default total excludes when
+    /// `total_excludes` is `None`.
+    pub fn new(
+        rate_unit: RateUnit,
+        proc_root: PathBuf,
+        total_excludes: Option<&[String]>,
+        quota: Option<u64>,
+        lenient: bool,
+    ) -> Result<Self> {
+        let total_excludes: Vec<Regex> = match total_excludes {
+            Some(globs) => globs
+                .iter()
+                .map(|glob| glob_regex(glob))
+                .collect::<Result<_>>(),
+            None => Ok(VIRTUAL_INTERFACES.clone()),
+        }?;
+
+        Ok(Self {
+            rate_unit,
+            proc_root,
+            total_excludes,
+            quota,
+            lenient,
+        })
+    }
+
+    fn is_excluded_from_total(&self, interface: &str) -> bool {
+        self.total_excludes
+            .iter()
+            .any(|exclude| exclude.is_match(interface))
+    }
+}
+
+impl Default for NetworkOptions {
+    fn default() -> Self {
+        Self::new(
+            RateUnit::Bytes,
+            PathBuf::from(DEFAULT_PROC_ROOT),
+            None,
+            None,
+            false,
+        )
+        .expect("default total excludes are valid globs")
+    }
+}
+
+/// Compiles an interface glob supporting `*` and `?` into an anchored regex.
+fn glob_regex(glob: &str) -> Result<Regex> {
+    let regex = format!(
+        "^{}$",
+        regex::escape(glob).replace(r"\*", ".*").replace(r"\?", ".")
+    );
+    Regex::new(&regex).with_context(|| format!("Invalid network interface glob '{glob}'"))
+}
+
+/// An interface selection from `--network`, written as
+/// `NAME[=ALIAS][:STAT,...]` (e.g. `en0=WIFI:rx,tx`, `utun*` or `auto`).
+#[derive(Debug)]
+pub struct InterfaceSpec {
+    pattern: InterfacePattern,
+    alias: Option<String>,
+    flags: Option<Vec<&'static str>>,
+}
+
+impl InterfaceSpec {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (selector, flags) = match spec.split_once(':') {
+            Some((selector, flags)) => (selector, Some(flags)),
+            None => (spec, None),
+        };
+        let (name, alias) = match selector.split_once('=') {
+            Some((name, alias)) => (name, Some(alias)),
+            None => (selector, None),
+        };
+
+        if name.is_empty() {
+            bail!("Network interface spec '{spec}' is missing an interface name");
+        }
+        if alias.is_some_and(|alias| network_key_suffix(alias).trim_matches('_').is_empty()) {
+            bail!("Network interface spec '{spec}' has an empty alias");
+        }
+
+        let pattern = if name == "auto" {
+            InterfacePattern::Primary
+        } else if name == "total" {
+            InterfacePattern::Total
+        } else if name.contains(['*', '?']) {
+            if alias.is_some() {
+                bail!("Network interface spec '{spec}' cannot alias a glob pattern");
+            }
+            InterfacePattern::Glob(glob_regex(name)?)
+        } else {
+            InterfacePattern::Name(name.to_string())
+        };
+
+        let flags = flags
+            .map(|flags| {
+                flags
+                    .split(',')
+                    .map(|flag| {
+                        ALL_NETWORK_FLAGS
+                            .iter()
+                            .copied()
+                            .find(|known| *known == flag.trim())
+                            .with_context(|| {
+                                format!(
+                                    "Unknown network stat '{flag}' in '{spec}'. Possible values: {}",
+                                    ALL_NETWORK_FLAGS.join(", ")
+                                )
+                            })
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?;
+
+        Ok(Self {
+            pattern,
+            alias: alias.map(str::to_string),
+            flags,
+        })
+    }
+
+    /// Returns the interface name when the spec names a single interface
+    /// rather than a glob.
+    pub fn exact_name(&self) -> Option<&str> {
+        match &self.pattern {
+            InterfacePattern::Name(name) => Some(name),
+            InterfacePattern::Glob(_) | InterfacePattern::Primary | InterfacePattern::Total => None,
+        }
+    }
+
+    /// Returns the active interfaces selected by this spec, sorted by name.
+    fn matching<'a>(&self, active_interfaces: &[&'a str]) -> Vec<&'a str> {
+        let mut matching: Vec<&str> = active_interfaces
+            .iter()
+            .copied()
+            .filter(|interface| match &self.pattern {
+                InterfacePattern::Name(name) => interface == name,
+                InterfacePattern::Glob(regex) => regex.is_match(interface),
+                InterfacePattern::Primary | InterfacePattern::Total => false,
+            })
+            .collect();
+        matching.sort_unstable();
+        matching
+    }
+
+    /// Returns the key suffix for `interface`, using the alias when set.
+    fn key_suffix(&self, interface: &str) -> String {
+        network_key_suffix(self.alias.as_deref().unwrap_or(interface))
+    }
+}
+
+/// Cumulative counters of a network interface at one point in time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct InterfaceCounters {
+    rx_bytes: u64,
+    tx_bytes: u64,
+    rx_packets: u64,
+    tx_packets: u64,
+    rx_errors: u64,
+    tx_errors: u64,
+}
+
+impl InterfaceCounters {
+    fn from_data(data: &NetworkData) -> Self {
+        Self {
+            rx_bytes: data.total_received(),
+            tx_bytes: data.total_transmitted(),
+            rx_packets: data.total_packets_received(),
+            tx_packets: data.total_packets_transmitted(),
+            rx_errors: data.total_errors_on_received(),
+            tx_errors: data.total_errors_on_transmitted(),
+        }
+    }
+}
+
+impl AddAssign for InterfaceCounters {
+    fn add_assign(&mut self, other: Self) {
+        self.rx_bytes += other.rx_bytes;
+        self.tx_bytes += other.tx_bytes;
+        self.rx_packets += other.rx_packets;
+        self.tx_packets += other.tx_packets;
+        self.rx_errors += other.rx_errors;
+        self.tx_errors += other.tx_errors;
+    }
+}
+
+/// Per-second rates of an interface since the previous tick.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct InterfaceRates {
+    rx_bytes: f64,
+    tx_bytes: f64,
+    rx_errors: f64,
+    tx_errors: f64,
+}
+
+impl AddAssign for InterfaceRates {
+    fn add_assign(&mut self, other: Self) {
+        self.rx_bytes += other.rx_bytes;
+        self.tx_bytes += other.tx_bytes;
+        self.rx_errors += other.rx_errors;
+        self.tx_errors += other.tx_errors;
+    }
+}
 
 /// Per-interface counters used to compute transfer rates between ticks.
 struct InterfaceBaseline {
-    rx_total: u64,
-    tx_total: u64,
+    counters: InterfaceCounters,
     at: Instant,
+    /// Set when the baseline may be many ticks old, so the next rates would
+    /// be averaged over the whole gap.
+    stale: bool,
 }
 
-/// Collector-owned baselines keyed by network interface name.
+/// Collector-owned baselines keyed by network interface name, along with the
+/// data usage accumulated from the byte deltas between them.
 #[derive(Default)]
 pub struct NetworkRateBaselines {
     by_interface: HashMap<String, InterfaceBaseline>,
+    usage: NetworkUsage,
+    /// Interface `auto` resolved to on the previous tick.
+    primary: Option<String>,
 }
 
 impl NetworkRateBaselines {
-    /// Resets the baseline for `interface` to the current cumulative totals.
-    fn reset(&mut self, interface: &str, rx_total: u64, tx_total: u64) {
+    pub fn with_usage(usage: NetworkUsage) -> Self {
+        Self {
+            by_interface: HashMap::new(),
+            usage,
+            primary: None,
+        }
+    }
+
+    pub fn usage_mut(&mut self) -> &mut NetworkUsage {
+        &mut self.usage
+    }
+
+    /// Resets the baseline for `interface` to the current cumulative counters.
+    fn reset(&mut self, interface: &str, counters: InterfaceCounters) {
         self.by_interface.insert(
             interface.to_owned(),
             InterfaceBaseline {
-                rx_total,
-                tx_total,
+                counters,
                 at: Instant::now(),
+                stale: false,
             },
         );
     }
 
+    /// Records the interface `auto` resolved to. When it switches to an
+    /// interface that was not followed since, that interface's baseline is
+    /// marked stale so its first rates are not averaged over the gap.
+    fn track_primary(&mut self, primary: Option<&str>) {
+        if self.primary.as_deref() == primary {
+            return;
+        }
+        if let Some(baseline) = primary.and_then(|primary| self.by_interface.get_mut(primary)) {
+            baseline.stale = true;
+        }
+        self.primary = primary.map(str::to_owned);
+    }
+
+    /// Computes the rates of `interface` since its previous baseline, adds the
+    /// transferred bytes to its usage and moves the baseline to `counters`.
+    fn update(&mut self, interface: &str, counters: InterfaceCounters) -> InterfaceRates {
+        let rates = match self.by_interface.get(interface) {
+            Some(baseline) => {
+                let elapsed = baseline.at.elapsed().as_secs_f64();
+                let previous = baseline.counters;
+                // Nothing is counted when the counters wrapped, matching the
+                // zero rates reported by `counter_rates`.
+                if let (Some(rx), Some(tx)) = (
+                    counters.rx_bytes.checked_sub(previous.rx_bytes),
+                    counters.tx_bytes.checked_sub(previous.tx_bytes),
+                ) {
+                    self.usage.record(interface, rx + tx);
+                }
+                if baseline.stale {
+                    self.reset(interface, counters);
+                    return InterfaceRates::default();
+                }
+                let (rx_bytes, tx_bytes) = counter_rates(
+                    Some(previous.rx_bytes),
+                    Some(previous.tx_bytes),
+                    counters.rx_bytes,
+                    counters.tx_bytes,
+                    elapsed,
+                );
+                let (rx_errors, tx_errors) = counter_rates(
+                    Some(previous.rx_errors),
+                    Some(previous.tx_errors),
+                    counters.rx_errors,
+                    counters.tx_errors,
+                    elapsed,
+                );
+                InterfaceRates {
+                    rx_bytes,
+                    tx_bytes,
+                    rx_errors,
+                    tx_errors,
+                }
+            }
+            None => InterfaceRates::default(),
+        };
+
+        self.reset(interface, counters);
+        rates
+    }
+
     /// Retains only baselines for interfaces that are currently active.
     pub fn retain_active(&mut self, active_interfaces: &[&str]) {
         self.by_interface
@@ -45,88 +394,423 @@ fn network_key_suffix(interface: &str) -> String {
         .collect()
 }
 
-/// Converts a byte delta and the elapsed time into a rate in `KiB/s`.
-fn rate_kib_per_sec(delta_bytes: u64, elapsed_secs: f64) -> u64 {
-    if elapsed_secs <= 0.0 {
-        return 0;
+/// Everything reported for one selected interface in a tick.
+#[derive(Clone, Copy, Default)]
+struct InterfaceStats<'a> {
+    counters: InterfaceCounters,
+    rates: InterfaceRates,
+    usage: InterfaceUsage,
+    /// The interface itself, used for its addresses. `None` for totals and
+    /// missing interfaces.
+    data: Option<&'a NetworkData>,
+}
+
+/// Returns the comma-separated addresses of `data` matching `family`, or
+/// `N/A` when there are none.
+fn format_addresses(data: Option<&NetworkData>, family: impl Fn(&IpAddr) -> bool) -> String {
+    let addresses: Vec<String> = data
+        .map(|data| data.ip_networks())
+        .unwrap_or_default()
+        .iter()
+        .filter(|network| family(&network.addr))
+        .map(|network| network.addr.to_string())
+        .collect();
+
+    if addresses.is_empty() {
+        "N/A".to_string()
+    } else {
+        addresses.join(",")
     }
-    ((delta_bytes as f64 / BYTES_PER_KB as f64) / elapsed_secs).round() as u64
 }
 
-/// Computes rx/tx rates in `KiB/s` from the previous and current cumulative totals.
-///
-/// Returns `(0, 0)` when there is no previous baseline (first sighting of an
-/// interface) or when the cumulative counters wrapped around.
-fn compute_rates(
-    prev_rx: Option<u64>,
-    prev_tx: Option<u64>,
-    rx_total: u64,
-    tx_total: u64,
-    elapsed_secs: f64,
-) -> (u64, u64) {
-    let (Some(prev_rx), Some(prev_tx)) = (prev_rx, prev_tx) else {
-        return (0, 0);
+/// Returns whether `addr` can reach beyond the machine and its link, i.e. is
+/// neither loopback nor link-local (such as the `fe80::` address every IPv6
+/// interface gets).
+fn is_routable(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => !addr.is_loopback() && !addr.is_link_local(),
+        IpAddr::V6(addr) => !addr.is_loopback() && !addr.is_unicast_link_local(),
+    }
+}
+
+/// Writes the selected `flags` for one interface.
+fn write_interface_stats(
+    buf: &mut String,
+    key_suffix: &str,
+    flags: &[&str],
+    stats: &InterfaceStats,
+    format: &FormatOptions,
+    options: &NetworkOptions,
+) {
+    let InterfaceStats {
+        counters,
+        rates,
+        usage,
+        data,
+    } = stats;
+    let format_rate = |bytes_per_sec: f64| match options.rate_unit {
+        RateUnit::Bytes => format.rate(bytes_per_sec),
+        RateUnit::Bits => format.bit_rate(bytes_per_sec),
     };
+    let per_sec = unit(format.no_units, "/s");
 
-    if rx_total < prev_rx || tx_total < prev_tx {
-        return (0, 0);
+    for &flag in flags {
+        match flag {
+            "rx" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_RX_{key_suffix}=\"{}\" ",
+                    format_rate(rates.rx_bytes)
+                );
+            }
+            "tx" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_TX_{key_suffix}=\"{}\" ",
+                    format_rate(rates.tx_bytes)
+                );
+            }
+            "total_rx" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_TOTAL_RX_{key_suffix}=\"{}\" ",
+                    format.size(counters.rx_bytes)
+                );
+            }
+            "total_tx" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_TOTAL_TX_{key_suffix}=\"{}\" ",
+                    format.size(counters.tx_bytes)
+                );
+            }
+            "packets_rx" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_PACKETS_RX_{key_suffix}=\"{}\" ",
+                    counters.rx_packets
+                );
+            }
+            "packets_tx" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_PACKETS_TX_{key_suffix}=\"{}\" ",
+                    counters.tx_packets
+                );
+            }
+            "errors_rx" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_ERRORS_RX_{key_suffix}=\"{}\" ",
+                    counters.rx_errors
+                );
+            }
+            "errors_tx" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_ERRORS_TX_{key_suffix}=\"{}\" ",
+                    counters.tx_errors
+                );
+            }
+            "errors_rx_rate" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_ERRORS_RX_RATE_{key_suffix}=\"{:.1}{per_sec}\" ",
+                    rates.rx_errors
+                );
+            }
+            "errors_tx_rate" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_ERRORS_TX_RATE_{key_suffix}=\"{:.1}{per_sec}\" ",
+                    rates.tx_errors
+                );
+            }
+            "today" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_{key_suffix}_TODAY=\"{}\" ",
+                    format.size(usage.today)
+                );
+            }
+            "month" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_{key_suffix}_MONTH=\"{}\" ",
+                    format.size(usage.month)
+                );
+            }
+            "month_usage" => match options.quota {
+                Some(quota) if quota > 0 => {
+                    let percentage = usage.month as f64 / quota as f64 * PERCENT as f64;
+                    let _ = write!(
+                        buf,
+                        "NETWORK_{key_suffix}_MONTH_USAGE=\"{percentage:.1}{}\" ",
+                        unit(format.no_units, "%")
+                    );
+                }
+                _ => {
+                    let _ = write!(buf, "NETWORK_{key_suffix}_MONTH_USAGE=\"N/A\" ");
+                }
+            },
+            "ipv4" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_IPV4_{key_suffix}=\"{}\" ",
+                    format_addresses(*data, IpAddr::is_ipv4)
+                );
+            }
+            "ipv6" => {
+                let _ = write!(
+                    buf,
+                    "NETWORK_IPV6_{key_suffix}=\"{}\" ",
+                    format_addresses(*data, IpAddr::is_ipv6)
+                );
+            }
+            "mac" => {
+                let mac =
+                    data.map_or_else(|| "N/A".to_string(), |data| data.mac_address().to_string());
+                let _ = write!(buf, "NETWORK_MAC_{key_suffix}=\"{mac}\" ");
+            }
+            "has_address" => {
+                let has_address = data.is_some_and(|data| {
+                    data.ip_networks()
+                        .iter()
+                        .any(|network| is_routable(&network.addr))
+                });
+                let _ = write!(buf, "NETWORK_HAS_ADDRESS_{key_suffix}=\"{has_address}\" ");
+            }
+            _ => {}
+        }
     }
+}
 
-    (
-        rate_kib_per_sec(rx_total - prev_rx, elapsed_secs),
-        rate_kib_per_sec(tx_total - prev_tx, elapsed_secs),
-    )
+/// Returns whether `interface` is a loopback interface (`lo`, `lo0`).
+fn is_loopback(interface: &str) -> bool {
+    interface
+        .strip_prefix("lo")
+        .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Returns the interface of the lowest-metric default route in the contents
+/// of `/proc/net/route`.
+fn parse_default_route(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (interface, destination, flags, metric, mask) = (
+                *fields.first()?,
+                *fields.get(1)?,
+                u32::from_str_radix(fields.get(3)?, 16).ok()?,
+                fields.get(6)?.parse::<u32>().ok()?,
+                *fields.get(7)?,
+            );
+            (destination == "00000000" && mask == "00000000" && flags & ROUTE_FLAG_UP != 0)
+                .then_some((metric, interface))
+        })
+        .min_by_key(|&(metric, _)| metric)
+        .map(|(_, interface)| interface.to_string())
+}
+
+/// Returns whether `interface` matches the default total excludes, such as
+/// tunnels, bridges and AirDrop links.
+fn is_virtual(interface: &str) -> bool {
+    VIRTUAL_INTERFACES
+        .iter()
+        .any(|pattern| pattern.is_match(interface))
+}
+
+/// Returns the non-loopback, non-virtual interface that moved the most bytes since the
+/// last refresh, falling back to the most bytes overall.
+fn busiest_interface<'a>(candidates: impl Iterator<Item = (&'a str, u64, u64)>) -> Option<&'a str> {
+    candidates
+        .filter(|(interface, _, _)| !is_loopback(interface) && !is_virtual(interface))
+        .max_by_key(|&(_, recent, total)| (recent, total))
+        .map(|(interface, _, _)| interface)
+}
+
+/// Returns the interface carrying the default route.
+///
+/// On Linux this is read from `net/route` under `proc_root`; elsewhere, or
+/// when no default route is up, the busiest interface is used instead.
+fn primary_interface<'a>(n: &'a Networks, proc_root: &Path) -> Option<&'a str> {
+    if cfg!(target_os = "linux")
+        && let Some(route) = fs::read_to_string(proc_root.join("net/route"))
+            .ok()
+            .and_then(|contents| parse_default_route(&contents))
+        && let Some((interface, _)) = n.get_key_value(&route)
+    {
+        return Some(interface);
+    }
+
+    busiest_interface(n.iter().map(|(interface, data)| {
+        (
+            interface.as_str(),
+            data.received() + data.transmitted(),
+            data.total_received() + data.total_transmitted(),
+        )
+    }))
+}
+
+/// What a selected `--network` entry reports on.
+enum Selection<'a> {
+    Interface(&'a str),
+    /// An interface named in lenient mode, reported with its state even
+    /// while it is missing.
+    Named(&'a str),
+    Total,
+}
+
+/// Returns the stats of `interface` for this tick, computing them only once
+/// even when the interface is selected several times.
+fn tick_stats<'a>(
+    n: &'a Networks,
+    interface: &'a str,
+    baselines: &mut NetworkRateBaselines,
+    cache: &mut HashMap<&'a str, InterfaceStats<'a>>,
+) -> Option<InterfaceStats<'a>> {
+    let data = n.get(interface)?;
+    Some(*cache.entry(interface).or_insert_with(|| {
+        let counters = InterfaceCounters::from_data(data);
+        let rates = baselines.update(interface, counters);
+        InterfaceStats {
+            counters,
+            rates,
+            usage: baselines.usage.get(interface),
+            data: Some(data),
+        }
+    }))
 }
 
 pub fn get_network_stats(
     n: &Networks,
-    interfaces: Option<&[String]>,
+    interfaces: Option<&[InterfaceSpec]>,
+    flags: &[&str],
     baselines: &mut NetworkRateBaselines,
-    no_units: bool,
+    format: &FormatOptions,
+    options: &NetworkOptions,
     buf: &mut String,
 ) {
     let active_interfaces: Vec<&str> = n.keys().map(|k| k.as_str()).collect();
     baselines.retain_active(&active_interfaces);
+    // Keep the previous date when the local date cannot be read, rather than
+    // resetting the usage.
+    if let Some(today) = LocalDate::today() {
+        baselines.usage.roll_over(today);
+    }
 
-    let interfaces_to_check: Vec<&str> = match interfaces {
-        Some(ifaces) => ifaces.iter().map(String::as_str).collect(),
-        None => active_interfaces,
-    };
-
-    let unit = unit(no_units, "KiB/s");
-
-    for interface in interfaces_to_check {
-        if let Some(data) = n.get(interface) {
-            let key_suffix = network_key_suffix(interface);
-            let rx_total = data.total_received();
-            let tx_total = data.total_transmitted();
-
-            let (rx_rate, tx_rate) = match baselines.by_interface.get(interface) {
-                Some(baseline) => {
-                    let elapsed = baseline.at.elapsed().as_secs_f64();
-                    let rates = compute_rates(
-                        Some(baseline.rx_total),
-                        Some(baseline.tx_total),
-                        rx_total,
-                        tx_total,
-                        elapsed,
-                    );
+    let mut selected: Vec<(Selection, String, &[&str])> = Vec::new();
+    match interfaces {
+        Some(specs) => {
+            for spec in specs {
+                let flags = spec.flags.as_deref().unwrap_or(flags);
+                match spec.pattern {
+                    InterfacePattern::Primary => {
+                        let key_suffix = spec.key_suffix(PRIMARY_KEY);
+                        let primary = primary_interface(n, &options.proc_root);
+                        baselines.track_primary(primary);
+                        let _ = write!(
+                            buf,
+                            "NETWORK_{key_suffix}_NAME=\"{}\" ",
+                            primary.unwrap_or("N/A")
+                        );
+                        if let Some(primary) = primary {
+                            selected.push((Selection::Interface(primary), key_suffix, flags));
+                        }
+                    }
+                    InterfacePattern::Total => {
+                        selected.push((Selection::Total, spec.key_suffix(TOTAL_KEY), flags));
+                    }
+                    InterfacePattern::Name(ref name) if options.lenient => {
+                        selected.push((Selection::Named(name), spec.key_suffix(name), flags));
+                    }
+                    _ => selected.extend(spec.matching(&active_interfaces).into_iter().map(
+                        |interface| {
+                            (
+                                Selection::Interface(interface),
+                                spec.key_suffix(interface),
+                                flags,
+                            )
+                        },
+                    )),
+                }
+            }
+        }
+        None => {
+            selected.extend(active_interfaces.iter().map(|&interface| {
+                (
+                    Selection::Interface(interface),
+                    network_key_suffix(interface),
+                    flags,
+                )
+            }));
+            selected.push((Selection::Total, TOTAL_KEY.to_string(), flags));
+        }
+    }
 
-                    baselines.reset(interface, rx_total, tx_total);
+    // `total` sums the interfaces selected alongside it, if any.
+    let sums_selected = interfaces.is_some_and(|specs| {
+        specs
+            .iter()
+            .any(|spec| !matches!(spec.pattern, InterfacePattern::Total))
+    });
+    let total_interfaces: Vec<&str> = if sums_selected {
+        let mut interfaces: Vec<&str> = selected
+            .iter()
+            .filter_map(|(selection, _, _)| match *selection {
+                Selection::Interface(interface) | Selection::Named(interface) => Some(interface),
+                Selection::Total => None,
+            })
+            .collect();
+        interfaces.sort_unstable();
+        interfaces.dedup();
+        interfaces
+    } else {
+        active_interfaces
+            .iter()
+            .copied()
+            .filter(|interface| !options.is_excluded_from_total(interface))
+            .collect()
+    };
 
-                    rates
-                }
-                None => {
-                    baselines.reset(interface, rx_total, tx_total);
-                    (0, 0)
-                }
-            };
+    let mut cache = HashMap::new();
+    for (selection, key_suffix, flags) in selected {
+        let stats = match selection {
+            Selection::Interface(interface) => tick_stats(n, interface, baselines, &mut cache),
+            Selection::Named(interface) => {
+                let stats = tick_stats(n, interface, baselines, &mut cache);
+                let state = if stats.is_some() { "up" } else { "down" };
+                let _ = write!(buf, "NETWORK_{key_suffix}_STATE=\"{state}\" ");
+                Some(stats.unwrap_or_else(|| InterfaceStats {
+                    usage: baselines.usage.get(interface),
+                    ..InterfaceStats::default()
+                }))
+            }
+            Selection::Total => Some(
+                total_interfaces
+                    .iter()
+                    .filter_map(|interface| tick_stats(n, interface, baselines, &mut cache))
+                    .fold(InterfaceStats::default(), |mut total, interface| {
+                        total.counters += interface.counters;
+                        total.rates += interface.rates;
+                        total.usage += interface.usage;
+                        total
+                    }),
+            ),
+        };
 
-            let _ = write!(
-                buf,
-                "NETWORK_RX_{}=\"{rx_rate}{unit}\" NETWORK_TX_{}=\"{tx_rate}{unit}\" ",
-                key_suffix, key_suffix
-            );
+        let flags: Vec<&str> = match selection {
+            Selection::Total => flags
+                .iter()
+                .copied()
+                .filter(|flag| !ADDRESS_FLAGS.contains(flag))
+                .collect(),
+            _ => flags.to_vec(),
+        };
+        if let Some(stats) = stats {
+            write_interface_stats(buf, &key_suffix, &flags, &stats, format, options);
         }
     }
 }
@@ -134,6 +818,7 @@ pub fn get_network_stats(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
 
     #[test]
     fn test_network_key_suffix_normalizes_interface_name() {
@@ -143,66 +828,678 @@ mod tests {
     }
 
     #[test]
-    fn test_rate_kib_per_sec_typical_rate() {
-        assert_eq!(rate_kib_per_sec(2048, 2.0), 1);
+    fn test_network_baselines_retain_active() {
+        let mut baselines = NetworkRateBaselines::default();
+        baselines.reset("en0", InterfaceCounters::default());
+        baselines.reset("utun0", InterfaceCounters::default());
+
+        baselines.retain_active(&["en0"]);
+
+        assert!(baselines.by_interface.contains_key("en0"));
+        assert!(!baselines.by_interface.contains_key("utun0"));
+    }
+
+    #[test]
+    fn test_get_network_stats_bits_rate_unit() {
+        let networks = Networks::new_with_refreshed_list();
+        let mut baselines = NetworkRateBaselines::default();
+        let mut buf = String::new();
+
+        get_network_stats(
+            &networks,
+            None,
+            &["rx", "tx"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &NetworkOptions {
+                rate_unit: RateUnit::Bits,
+                ..NetworkOptions::default()
+            },
+            &mut buf,
+        );
+
+        for pair in buf.split_whitespace() {
+            assert!(pair.ends_with("Kbit/s\""), "unexpected pair: {pair}");
+        }
+    }
+
+    fn counters() -> InterfaceCounters {
+        InterfaceCounters {
+            rx_bytes: 3 * 1024 * 1024 * 1024,
+            tx_bytes: 512 * 1024 * 1024,
+            rx_packets: 1200,
+            tx_packets: 800,
+            rx_errors: 7,
+            tx_errors: 0,
+        }
+    }
+
+    #[test]
+    fn test_baselines_update_computes_error_rates() {
+        let mut baselines = NetworkRateBaselines::default();
+        let first = counters();
+
+        assert_eq!(baselines.update("en0", first), InterfaceRates::default());
+
+        // Pretend the previous tick happened two seconds ago.
+        baselines.by_interface.get_mut("en0").unwrap().at -= Duration::from_secs(2);
+        let second = InterfaceCounters {
+            rx_errors: first.rx_errors + 10,
+            ..first
+        };
+        let rates = baselines.update("en0", second);
+
+        assert!((rates.rx_errors - 5.0).abs() < 0.1, "{rates:?}");
+        assert_eq!(rates.tx_errors, 0.0);
+        assert_eq!(baselines.by_interface["en0"].counters, second);
+    }
+
+    #[test]
+    fn test_track_primary_drops_rates_over_a_stale_baseline() {
+        let mut baselines = NetworkRateBaselines::default();
+        let first = counters();
+        baselines.track_primary(Some("en0"));
+        baselines.update("en0", first);
+        baselines.update("en1", first);
+
+        // `auto` switches to en1, whose baseline was last refreshed long ago.
+        baselines.by_interface.get_mut("en1").unwrap().at -= Duration::from_secs(600);
+        baselines.track_primary(Some("en1"));
+        let second = InterfaceCounters {
+            rx_bytes: first.rx_bytes + 6_000_000,
+            ..first
+        };
+
+        assert_eq!(baselines.update("en1", second), InterfaceRates::default());
+        assert!(!baselines.by_interface["en1"].stale);
+
+        // The following tick reports rates again.
+        baselines.by_interface.get_mut("en1").unwrap().at -= Duration::from_secs(2);
+        let third = InterfaceCounters {
+            rx_bytes: second.rx_bytes + 2048,
+            ..second
+        };
+        assert!(baselines.update("en1", third).rx_bytes > 0.0);
+
+        // Resolving to the same interface again keeps its baseline.
+        baselines.track_primary(Some("en1"));
+        assert!(!baselines.by_interface["en1"].stale);
+    }
+
+    #[test]
+    fn test_write_interface_stats_totals_packets_and_errors() {
+        let rates = InterfaceRates {
+            rx_errors: 0.5,
+            ..InterfaceRates::default()
+        };
+        let mut buf = String::new();
+
+        write_interface_stats(
+            &mut buf,
+            "en0",
+            &[
+                "total_rx",
+                "total_tx",
+                "packets_rx",
+                "packets_tx",
+                "errors_rx",
+                "errors_tx",
+                "errors_rx_rate",
+                "errors_tx_rate",
+            ],
+            &InterfaceStats {
+                counters: counters(),
+                rates,
+                ..InterfaceStats::default()
+            },
+            &FormatOptions::default(),
+            &NetworkOptions::default(),
+        );
+
+        assert_eq!(
+            buf,
+            "NETWORK_TOTAL_RX_en0=\"3.0GB\" NETWORK_TOTAL_TX_en0=\"0.5GB\" \
+             NETWORK_PACKETS_RX_en0=\"1200\" NETWORK_PACKETS_TX_en0=\"800\" \
+             NETWORK_ERRORS_RX_en0=\"7\" NETWORK_ERRORS_TX_en0=\"0\" \
+             NETWORK_ERRORS_RX_RATE_en0=\"0.5/s\" NETWORK_ERRORS_TX_RATE_en0=\"0.0/s\" "
+        );
     }
 
     #[test]
-    fn test_rate_kib_per_sec_sub_kib_rate_rounds_down() {
-        assert_eq!(rate_kib_per_sec(500, 1.0), 0);
+    fn test_write_interface_stats_follows_flag_order() {
+        let mut buf = String::new();
+
+        write_interface_stats(
+            &mut buf,
+            "en0",
+            &["tx", "errors_rx"],
+            &InterfaceStats {
+                counters: counters(),
+                ..InterfaceStats::default()
+            },
+            &FormatOptions {
+                no_units: true,
+                ..FormatOptions::default()
+            },
+            &NetworkOptions::default(),
+        );
+
+        assert_eq!(buf, "NETWORK_TX_en0=\"0\" NETWORK_ERRORS_RX_en0=\"7\" ");
     }
 
     #[test]
-    fn test_rate_kib_per_sec_rounds_to_nearest() {
-        assert_eq!(rate_kib_per_sec(3072, 1.0), 3);
+    fn test_get_network_stats_uses_spec_alias_and_flags() {
+        let networks = Networks::new_with_refreshed_list();
+        let Some(interface) = networks.keys().next() else {
+            return;
+        };
+        let specs = [InterfaceSpec::parse(&format!("{interface}=PRIMARY:total_rx")).unwrap()];
+        let mut baselines = NetworkRateBaselines::default();
+        let mut buf = String::new();
+
+        get_network_stats(
+            &networks,
+            Some(&specs),
+            &["rx", "tx"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &NetworkOptions::default(),
+            &mut buf,
+        );
+
+        assert!(buf.starts_with("NETWORK_TOTAL_RX_PRIMARY=\""), "{buf}");
+        assert!(!buf.contains("NETWORK_RX_"), "{buf}");
     }
 
     #[test]
-    fn test_rate_kib_per_sec_zero_elapsed() {
-        assert_eq!(rate_kib_per_sec(1024, 0.0), 0);
+    fn test_interface_spec_parse_plain_name() {
+        let spec = InterfaceSpec::parse("en0").unwrap();
+
+        assert_eq!(spec.exact_name(), Some("en0"));
+        assert_eq!(spec.key_suffix("en0"), "en0");
+        assert!(spec.flags.is_none());
     }
 
     #[test]
-    fn test_rate_kib_per_sec_pins_1024_divisor() {
-        assert_eq!(rate_kib_per_sec(1500, 1.0), 1);
+    fn test_interface_spec_parse_alias_and_flags() {
+        let spec = InterfaceSpec::parse("en0=WIFI:rx,tx,total_rx").unwrap();
+
+        assert_eq!(spec.exact_name(), Some("en0"));
+        assert_eq!(spec.key_suffix("en0"), "WIFI");
+        assert_eq!(spec.flags, Some(vec!["rx", "tx", "total_rx"]));
     }
 
     #[test]
-    fn test_rate_kib_per_sec_fractional_sub_kib_rate() {
-        // 1000 bytes over 0.5s = 2000 B/s = 1.953 KiB/s -> rounds to 2 (would fail with integer div)
-        assert_eq!(rate_kib_per_sec(1000, 0.5), 2);
+    fn test_interface_spec_parse_glob() {
+        let spec = InterfaceSpec::parse("utun*:rx").unwrap();
+
+        assert_eq!(spec.exact_name(), None);
+        assert_eq!(
+            spec.matching(&["utun2", "en0", "utun10", "xutun1"]),
+            vec!["utun10", "utun2"]
+        );
+        assert_eq!(spec.key_suffix("utun2"), "utun2");
+
+        let single = InterfaceSpec::parse("en?").unwrap();
+        assert_eq!(single.matching(&["en0", "en10", "bridge.0"]), vec!["en0"]);
     }
 
     #[test]
-    fn test_network_baselines_retain_active() {
+    fn test_interface_spec_parse_rejects_invalid_specs() {
+        assert!(InterfaceSpec::parse("").is_err());
+        assert!(InterfaceSpec::parse(":rx").is_err());
+        assert!(InterfaceSpec::parse("en0=").is_err());
+        assert!(InterfaceSpec::parse("en0:rx,bogus").is_err());
+        assert!(InterfaceSpec::parse("en0:").is_err());
+        assert!(InterfaceSpec::parse("utun*=VPN").is_err());
+    }
+
+    const ROUTE_TABLE: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+tun0\t00000000\t00000000\t0000\t0\t0\t50\t00000000\t0\t0\t0
+";
+
+    #[test]
+    fn test_parse_default_route_picks_lowest_metric_up_route() {
+        assert_eq!(parse_default_route(ROUTE_TABLE), Some("eth0".to_string()));
+    }
+
+    #[test]
+    fn test_parse_default_route_without_default_route() {
+        let header = ROUTE_TABLE.lines().next().unwrap();
+        let local_only =
+            format!("{header}\neth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0\n");
+
+        assert_eq!(parse_default_route(&local_only), None);
+        assert_eq!(parse_default_route(""), None);
+    }
+
+    #[test]
+    fn test_busiest_interface_skips_loopback() {
+        let candidates = [
+            ("lo0", 9_000, 90_000),
+            ("en0", 500, 1_000),
+            ("en1", 500, 5_000),
+            ("utun0", 10, 100_000),
+        ];
+
+        assert_eq!(busiest_interface(candidates.into_iter()), Some("en1"));
+        assert_eq!(
+            busiest_interface([("utun3", 9_000, 9_000), ("awdl0", 1, 1)].into_iter()),
+            None
+        );
+        assert_eq!(busiest_interface([("lo", 1, 1)].into_iter()), None);
+    }
+
+    #[test]
+    fn test_is_loopback() {
+        assert!(is_loopback("lo"));
+        assert!(is_loopback("lo0"));
+        assert!(!is_loopback("low0"));
+        assert!(!is_loopback("en0"));
+    }
+
+    #[test]
+    fn test_interface_spec_parse_auto() {
+        let spec = InterfaceSpec::parse("auto:rx").unwrap();
+
+        assert!(matches!(spec.pattern, InterfacePattern::Primary));
+        assert_eq!(spec.exact_name(), None);
+        assert_eq!(spec.key_suffix(PRIMARY_KEY), "PRIMARY");
+        assert_eq!(spec.flags, Some(vec!["rx"]));
+    }
+
+    #[test]
+    fn test_get_network_stats_auto_emits_primary_name() {
+        let networks = Networks::new_with_refreshed_list();
+        let specs = [InterfaceSpec::parse("auto").unwrap()];
         let mut baselines = NetworkRateBaselines::default();
-        baselines.reset("en0", 100, 100);
-        baselines.reset("utun0", 200, 200);
+        let mut buf = String::new();
 
-        baselines.retain_active(&["en0"]);
+        get_network_stats(
+            &networks,
+            Some(&specs),
+            &["rx", "tx"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &NetworkOptions::default(),
+            &mut buf,
+        );
 
-        assert!(baselines.by_interface.contains_key("en0"));
-        assert!(!baselines.by_interface.contains_key("utun0"));
+        assert!(buf.starts_with("NETWORK_PRIMARY_NAME=\""), "{buf}");
+        if !buf.contains("NETWORK_PRIMARY_NAME=\"N/A\"") {
+            assert!(buf.contains("NETWORK_RX_PRIMARY="), "{buf}");
+            assert!(buf.contains("NETWORK_TX_PRIMARY="), "{buf}");
+        }
     }
 
     #[test]
-    fn test_compute_rates_first_sighting_returns_zero() {
-        assert_eq!(compute_rates(None, None, 1000, 2000, 1.0), (0, 0));
+    fn test_interface_spec_parse_total() {
+        let spec = InterfaceSpec::parse("total:rx,tx").unwrap();
+
+        assert!(matches!(spec.pattern, InterfacePattern::Total));
+        assert_eq!(spec.exact_name(), None);
+        assert_eq!(spec.key_suffix(TOTAL_KEY), "TOTAL");
+    }
+
+    #[test]
+    fn test_network_options_default_total_excludes() {
+        let options = NetworkOptions::default();
+
+        for interface in [
+            "lo", "lo0", "utun3", "awdl0", "bridge0", "docker0", "veth1a2b",
+        ] {
+            assert!(options.is_excluded_from_total(interface), "{interface}");
+        }
+        for interface in ["en0", "eth0", "wlan0", "enp3s0", "ap0", "apcli0"] {
+            assert!(!options.is_excluded_from_total(interface), "{interface}");
+        }
+    }
+
+    #[test]
+    fn test_network_options_custom_total_excludes_replace_defaults() {
+        let excludes = vec!["en1".to_string(), "wl*".to_string()];
+        let options = NetworkOptions::new(
+            RateUnit::Bytes,
+            PathBuf::from("/proc"),
+            Some(&excludes),
+            None,
+            false,
+        )
+        .unwrap();
+
+        assert!(options.is_excluded_from_total("en1"));
+        assert!(options.is_excluded_from_total("wlan0"));
+        assert!(!options.is_excluded_from_total("lo0"));
+        assert!(!options.is_excluded_from_total("en0"));
+    }
+
+    #[test]
+    fn test_get_network_stats_total_sums_included_interfaces() {
+        let networks = Networks::new_with_refreshed_list();
+        let Some(interface) = networks.keys().next() else {
+            return;
+        };
+        let data = &networks[interface];
+        let expected = FormatOptions::default().size(data.total_received());
+        let specs = [InterfaceSpec::parse("total:total_rx").unwrap()];
+        let excludes: Vec<String> = networks
+            .keys()
+            .filter(|name| *name != interface)
+            .cloned()
+            .collect();
+        let options = NetworkOptions::new(
+            RateUnit::Bytes,
+            PathBuf::from("/proc"),
+            Some(&excludes),
+            None,
+            false,
+        )
+        .unwrap();
+        let mut baselines = NetworkRateBaselines::default();
+        let mut buf = String::new();
+
+        get_network_stats(
+            &networks,
+            Some(&specs),
+            &["rx", "tx"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &options,
+            &mut buf,
+        );
+
+        assert_eq!(buf, format!("NETWORK_TOTAL_RX_TOTAL=\"{expected}\" "));
     }
 
     #[test]
-    fn test_compute_rates_counter_wrap_returns_zero() {
+    fn test_get_network_stats_total_sums_selected_interfaces() {
+        let networks = Networks::new_with_refreshed_list();
+        let Some(interface) = networks.keys().next() else {
+            return;
+        };
+        let data = &networks[interface];
+        let expected = FormatOptions::default().size(data.total_received());
+        // Excludes only shape the total of all interfaces.
+        let specs = [
+            InterfaceSpec::parse(&format!("{interface}=SELECTED:rx")).unwrap(),
+            InterfaceSpec::parse("total:total_rx").unwrap(),
+        ];
+        let excludes = vec!["*".to_string()];
+        let options = NetworkOptions::new(
+            RateUnit::Bytes,
+            PathBuf::from("/proc"),
+            Some(&excludes),
+            None,
+            false,
+        )
+        .unwrap();
+        let mut baselines = NetworkRateBaselines::default();
+        let mut buf = String::new();
+
+        get_network_stats(
+            &networks,
+            Some(&specs),
+            &["rx", "tx"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &options,
+            &mut buf,
+        );
+
+        assert!(
+            buf.ends_with(&format!("NETWORK_TOTAL_RX_TOTAL=\"{expected}\" ")),
+            "{buf}"
+        );
+    }
+
+    #[test]
+    fn test_interface_counters_and_rates_add_up() {
+        let mut sum = counters();
+        sum += counters();
+        assert_eq!(sum.rx_packets, 2400);
+        assert_eq!(sum.rx_errors, 14);
+
+        let mut rates = InterfaceRates {
+            rx_bytes: 1.5,
+            ..InterfaceRates::default()
+        };
+        rates += InterfaceRates {
+            rx_bytes: 2.0,
+            tx_bytes: 1.0,
+            ..InterfaceRates::default()
+        };
+        assert_eq!(rates.rx_bytes, 3.5);
+        assert_eq!(rates.tx_bytes, 1.0);
+    }
+
+    #[test]
+    fn test_get_network_stats_lenient_reports_missing_interface_down() {
+        let networks = Networks::new_with_refreshed_list();
+        let specs = [InterfaceSpec::parse("utun-missing-xyz=VPN").unwrap()];
+        let options =
+            NetworkOptions::new(RateUnit::Bytes, PathBuf::from("/proc"), None, None, true).unwrap();
+        let mut baselines = NetworkRateBaselines::default();
+        let mut buf = String::new();
+
+        get_network_stats(
+            &networks,
+            Some(&specs),
+            &["rx", "tx"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &options,
+            &mut buf,
+        );
+
         assert_eq!(
-            compute_rates(Some(1000), Some(2000), 500, 2500, 1.0),
-            (0, 0)
+            buf,
+            "NETWORK_VPN_STATE=\"down\" NETWORK_RX_VPN=\"0KiB/s\" NETWORK_TX_VPN=\"0KiB/s\" "
         );
     }
 
     #[test]
-    fn test_compute_rates_normal_delta() {
+    fn test_get_network_stats_lenient_reports_present_interface_up() {
+        let networks = Networks::new_with_refreshed_list();
+        let Some(interface) = networks.keys().next() else {
+            return;
+        };
+        let specs = [InterfaceSpec::parse(&format!("{interface}=LINK:packets_rx")).unwrap()];
+        let options =
+            NetworkOptions::new(RateUnit::Bytes, PathBuf::from("/proc"), None, None, true).unwrap();
+        let mut baselines = NetworkRateBaselines::default();
+        let mut buf = String::new();
+
+        get_network_stats(
+            &networks,
+            Some(&specs),
+            &["rx", "tx"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &options,
+            &mut buf,
+        );
+
+        assert!(
+            buf.starts_with("NETWORK_LINK_STATE=\"up\" NETWORK_PACKETS_RX_LINK="),
+            "{buf}"
+        );
+    }
+
+    #[test]
+    fn test_get_network_stats_strict_skips_missing_interface() {
+        let networks = Networks::new_with_refreshed_list();
+        let specs = [InterfaceSpec::parse("utun-missing-xyz").unwrap()];
+        let mut baselines = NetworkRateBaselines::default();
+        let mut buf = String::new();
+
+        get_network_stats(
+            &networks,
+            Some(&specs),
+            &["rx", "tx"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &NetworkOptions::default(),
+            &mut buf,
+        );
+
+        assert_eq!(buf, "");
+    }
+
+    #[test]
+    fn test_write_interface_stats_addresses_without_interface() {
+        let mut buf = String::new();
+
+        write_interface_stats(
+            &mut buf,
+            "VPN",
+            &["ipv4", "ipv6", "mac", "has_address"],
+            &InterfaceStats::default(),
+            &FormatOptions::default(),
+            &NetworkOptions::default(),
+        );
+
         assert_eq!(
-            compute_rates(Some(2048), Some(4096), 4096, 6144, 1.0),
-            (2, 2)
+            buf,
+            "NETWORK_IPV4_VPN=\"N/A\" NETWORK_IPV6_VPN=\"N/A\" \
+             NETWORK_MAC_VPN=\"N/A\" NETWORK_HAS_ADDRESS_VPN=\"false\" "
+        );
+    }
+
+    #[test]
+    fn test_is_routable_skips_loopback_and_link_local() {
+        for addr in ["192.168.1.20", "10.8.0.2", "2001:db8::1", "fd00::2"] {
+            assert!(is_routable(&addr.parse().unwrap()), "{addr}");
+        }
+        for addr in [
+            "127.0.0.1",
+            "169.254.10.1",
+            "::1",
+            "fe80::1c2b:3aff:fe4d:5e6f",
+        ] {
+            assert!(!is_routable(&addr.parse().unwrap()), "{addr}");
+        }
+    }
+
+    #[test]
+    fn test_get_network_stats_total_skips_address_flags() {
+        let networks = Networks::new_with_refreshed_list();
+        let specs = [InterfaceSpec::parse("total:rx,ipv4,ipv6,mac,has_address").unwrap()];
+        let mut baselines = NetworkRateBaselines::default();
+        let mut buf = String::new();
+
+        get_network_stats(
+            &networks,
+            Some(&specs),
+            &["rx", "tx"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &NetworkOptions::default(),
+            &mut buf,
+        );
+
+        assert_eq!(buf, "NETWORK_RX_TOTAL=\"0KiB/s\" ");
+    }
+
+    #[test]
+    fn test_get_network_stats_addresses_match_interface() {
+        let networks = Networks::new_with_refreshed_list();
+        let Some((interface, data)) = networks.iter().find(|(_, data)| {
+            data.ip_networks()
+                .iter()
+                .any(|ip| ip.addr.is_ipv4() && is_routable(&ip.addr))
+        }) else {
+            return;
+        };
+        let specs =
+            [InterfaceSpec::parse(&format!("{interface}=IF:ipv4,mac,has_address")).unwrap()];
+        let mut baselines = NetworkRateBaselines::default();
+        let mut buf = String::new();
+
+        get_network_stats(
+            &networks,
+            Some(&specs),
+            &["rx", "tx"],
+            &mut baselines,
+            &FormatOptions::default(),
+            &NetworkOptions::default(),
+            &mut buf,
+        );
+
+        let ipv4 = format_addresses(Some(data), IpAddr::is_ipv4);
+        assert!(!ipv4.contains(':'), "{ipv4}");
+        assert_eq!(
+            buf,
+            format!(
+                "NETWORK_IPV4_IF=\"{ipv4}\" NETWORK_MAC_IF=\"{}\" NETWORK_HAS_ADDRESS_IF=\"true\" ",
+                data.mac_address()
+            )
+        );
+    }
+
+    #[test]
+    fn test_baselines_update_records_usage() {
+        let mut baselines = NetworkRateBaselines::default();
+        let first = counters();
+        baselines.update("en0", first);
+        assert_eq!(baselines.usage.get("en0").today, 0);
+
+        baselines.update(
+            "en0",
+            InterfaceCounters {
+                rx_bytes: first.rx_bytes + 1_000,
+                tx_bytes: first.tx_bytes + 24,
+                ..first
+            },
+        );
+        assert_eq!(baselines.usage.get("en0").today, 1_024);
+
+        // A counter wrap is not counted.
+        baselines.update("en0", InterfaceCounters::default());
+        assert_eq!(baselines.usage.get("en0").month, 1_024);
+    }
+
+    #[test]
+    fn test_write_interface_stats_usage_and_quota() {
+        let stats = InterfaceStats {
+            usage: InterfaceUsage {
+                today: 536_870_912,
+                month: 5_368_709_120,
+            },
+            ..InterfaceStats::default()
+        };
+        let with_quota = NetworkOptions {
+            quota: Some(20 * 1_073_741_824),
+            ..NetworkOptions::default()
+        };
+        let flags = ["today", "month", "month_usage"];
+
+        let mut buf = String::new();
+        write_interface_stats(
+            &mut buf,
+            "en0",
+            &flags,
+            &stats,
+            &FormatOptions::default(),
+            &with_quota,
+        );
+        assert_eq!(
+            buf,
+            "NETWORK_en0_TODAY=\"0.5GB\" NETWORK_en0_MONTH=\"5.0GB\" NETWORK_en0_MONTH_USAGE=\"25.0%\" "
+        );
+
+        let mut buf = String::new();
+        write_interface_stats(
+            &mut buf,
+            "en0",
+            &["month_usage"],
+            &stats,
+            &FormatOptions::default(),
+            &NetworkOptions::default(),
         );
+        assert_eq!(buf, "NETWORK_en0_MONTH_USAGE=\"N/A\" ");
     }
 }
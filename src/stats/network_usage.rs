@@ -0,0 +1,467 @@
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::ops::AddAssign;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How often the usage state file is rewritten while running.
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// First line of the usage state file.
+const STATE_HEADER: &str = "# stats_provider network usage";
+
+/// A calendar date in local time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalDate {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl LocalDate {
+    /// Stand-in for today when the local date cannot be determined at startup;
+    /// the first successful lookup rolls over from it.
+    const FALLBACK: Self = Self {
+        year: 1970,
+        month: 1,
+        day: 1,
+    };
+
+    /// Returns today's date in the local time zone, or `None` when the clock
+    /// or time zone cannot be read.
+    pub fn today() -> Option<Self> {
+        // SAFETY: `time` accepts a null pointer, and `localtime_r` only writes
+        // into the `tm` owned by this function.
+        let tm = unsafe {
+            let now = libc::time(std::ptr::null_mut());
+            if now == -1 {
+                return None;
+            }
+            let mut tm: libc::tm = std::mem::zeroed();
+            if libc::localtime_r(&now, &mut tm).is_null() {
+                return None;
+            }
+            tm
+        };
+
+        Self::from_tm(&tm)
+    }
+
+    /// Converts a broken-down time, rejecting out-of-range fields such as the
+    /// day 0 of a zeroed `tm`.
+    fn from_tm(tm: &libc::tm) -> Option<Self> {
+        let date = Self {
+            year: tm.tm_year + 1900,
+            month: u32::try_from(tm.tm_mon + 1).ok()?,
+            day: u32::try_from(tm.tm_mday).ok()?,
+        };
+        date.is_valid().then_some(date)
+    }
+
+    fn is_valid(self) -> bool {
+        (1..=12).contains(&self.month) && (1..=31).contains(&self.day)
+    }
+
+    /// Returns the first day of the billing month containing this date, for
+    /// billing months starting on `reset_day`.
+    fn period_start(self, reset_day: u32) -> Self {
+        let (year, month) = match (self.day >= reset_day, self.month) {
+            (true, month) => (self.year, month),
+            (false, 1) => (self.year - 1, 12),
+            (false, month) => (self.year, month - 1),
+        };
+
+        Self {
+            year,
+            month,
+            day: reset_day,
+        }
+    }
+
+    /// Parses a `YYYY-MM-DD` date.
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(3, '-');
+        let date = Self {
+            year: parts.next()?.parse().ok()?,
+            month: parts.next()?.parse().ok()?,
+            day: parts.next()?.parse().ok()?,
+        };
+        date.is_valid().then_some(date)
+    }
+}
+
+impl fmt::Display for LocalDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Bytes transferred (received and transmitted) by an interface.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct InterfaceUsage {
+    pub(super) today: u64,
+    pub(super) month: u64,
+}
+
+impl AddAssign for InterfaceUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.today += other.today;
+        self.month += other.month;
+    }
+}
+
+/// Daily and billing-month data usage per interface, optionally persisted to
+/// a state file so it survives restarts.
+pub struct NetworkUsage {
+    path: Option<PathBuf>,
+    reset_day: u32,
+    date: LocalDate,
+    by_interface: HashMap<String, InterfaceUsage>,
+    last_saved: Option<Instant>,
+}
+
+impl Default for NetworkUsage {
+    fn default() -> Self {
+        Self {
+            path: None,
+            reset_day: 1,
+            date: LocalDate::today().unwrap_or(LocalDate::FALLBACK),
+            by_interface: HashMap::new(),
+            last_saved: None,
+        }
+    }
+}
+
+impl NetworkUsage {
+    /// Creates the accumulator, loading the usage saved in `path` if it exists.
+    /// A corrupt file is reported on stderr and usage starts from scratch.
+    pub fn load(path: Option<PathBuf>, reset_day: u32) -> Result<Self> {
+        let mut usage = Self {
+            path,
+            reset_day,
+            ..Self::default()
+        };
+
+        if let Some(path) = usage.path.clone() {
+            let restored = match fs::read_to_string(&path) {
+                Ok(contents) => usage.restore(&contents),
+                Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+                Err(err) if err.kind() == ErrorKind::InvalidData => Err(err.into()),
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("Failed to read network usage file {}", path.display())
+                    });
+                }
+            };
+            if let Err(err) = restored {
+                eprintln!(
+                    "Warning: ignoring invalid network usage file {}: {err:#}",
+                    path.display()
+                );
+                usage.date = Self::default().date;
+                usage.by_interface.clear();
+            }
+        }
+
+        if let Some(today) = LocalDate::today() {
+            usage.roll_over(today);
+        }
+        Ok(usage)
+    }
+
+    /// Restores the date and per-interface usage from a saved state.
+    fn restore(&mut self, contents: &str) -> Result<()> {
+        for line in contents.lines().filter(|line| !line.starts_with('#')) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields.as_slice() {
+                ["date", date] => {
+                    self.date =
+                        LocalDate::parse(date).with_context(|| format!("Invalid date '{date}'"))?;
+                }
+                ["interface", interface, today, month] => {
+                    let usage = InterfaceUsage {
+                        today: today.parse().context("Invalid daily usage")?,
+                        month: month.parse().context("Invalid monthly usage")?,
+                    };
+                    self.by_interface.insert(interface.to_string(), usage);
+                }
+                [] | [""] => {}
+                _ => bail!("Unexpected line '{line}'"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serializes the current state in the format read by `restore`.
+    fn serialize(&self) -> String {
+        let mut interfaces: Vec<_> = self.by_interface.iter().collect();
+        interfaces.sort_by_key(|(interface, _)| interface.as_str());
+
+        let mut contents = format!("{STATE_HEADER}\ndate\t{}\n", self.date);
+        for (interface, usage) in interfaces {
+            contents.push_str(&format!(
+                "interface\t{interface}\t{}\t{}\n",
+                usage.today, usage.month
+            ));
+        }
+        contents
+    }
+
+    /// Moves the accumulator to `today`, clearing the daily usage on a new day
+    /// and the monthly usage on a new billing month.
+    pub(super) fn roll_over(&mut self, today: LocalDate) {
+        if today == self.date {
+            return;
+        }
+
+        let new_period =
+            today.period_start(self.reset_day) != self.date.period_start(self.reset_day);
+        for usage in self.by_interface.values_mut() {
+            usage.today = 0;
+            if new_period {
+                usage.month = 0;
+            }
+        }
+        self.date = today;
+        // Persist the reset right away rather than at the next save interval.
+        self.last_saved = None;
+    }
+
+    /// Adds `bytes` transferred by `interface` since the previous tick.
+    pub(super) fn record(&mut self, interface: &str, bytes: u64) {
+        let usage = self.by_interface.entry(interface.to_owned()).or_default();
+        usage.today += bytes;
+        usage.month += bytes;
+    }
+
+    pub(super) fn get(&self, interface: &str) -> InterfaceUsage {
+        self.by_interface
+            .get(interface)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Writes the state file if one is configured.
+    pub fn save(&mut self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        // Write to a temporary file first so a crash never leaves a truncated
+        // state file behind.
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, self.serialize())
+            .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+        fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+
+        self.last_saved = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Writes the state file when it has not been written for a while.
+    pub fn save_if_due(&mut self) -> Result<()> {
+        if self
+            .last_saved
+            .is_some_and(|saved| saved.elapsed() < SAVE_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> LocalDate {
+        LocalDate { year, month, day }
+    }
+
+    fn usage_on(today: LocalDate, reset_day: u32) -> NetworkUsage {
+        NetworkUsage {
+            reset_day,
+            date: today,
+            ..NetworkUsage::default()
+        }
+    }
+
+    #[test]
+    fn test_period_start() {
+        assert_eq!(date(2026, 10, 18).period_start(1), date(2026, 10, 1));
+        assert_eq!(date(2026, 10, 18).period_start(18), date(2026, 10, 18));
+        assert_eq!(date(2026, 10, 17).period_start(18), date(2026, 9, 18));
+        assert_eq!(date(2026, 1, 5).period_start(15), date(2025, 12, 15));
+    }
+
+    #[test]
+    fn test_local_date_parse_and_display() {
+        assert_eq!(LocalDate::parse("2026-03-07"), Some(date(2026, 3, 7)));
+        assert_eq!(date(2026, 3, 7).to_string(), "2026-03-07");
+        assert_eq!(LocalDate::parse("2026-13-01"), None);
+        assert_eq!(LocalDate::parse("yesterday"), None);
+        assert_eq!(LocalDate::parse("2026-03-00"), None);
+    }
+
+    #[test]
+    fn test_local_date_from_tm_rejects_day_zero() {
+        // SAFETY: `tm` is plain data, valid when zeroed.
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        // A zeroed `tm`, as left by a failed `localtime_r`, is day 0 of 1900.
+        assert_eq!(LocalDate::from_tm(&tm), None);
+
+        tm.tm_year = 126;
+        tm.tm_mon = 9;
+        tm.tm_mday = 18;
+        assert_eq!(LocalDate::from_tm(&tm), Some(date(2026, 10, 18)));
+    }
+
+    #[test]
+    fn test_local_date_today_is_plausible() {
+        let today = LocalDate::today().expect("local date is available");
+
+        assert!(today.year >= 2024, "{today}");
+        assert!((1..=12).contains(&today.month));
+        assert!((1..=31).contains(&today.day));
+    }
+
+    #[test]
+    fn test_record_accumulates_today_and_month() {
+        let mut usage = usage_on(date(2026, 10, 18), 1);
+
+        usage.record("en0", 100);
+        usage.record("en0", 50);
+        usage.record("en1", 7);
+
+        assert_eq!(
+            usage.get("en0"),
+            InterfaceUsage {
+                today: 150,
+                month: 150
+            }
+        );
+        assert_eq!(usage.get("en1").today, 7);
+        assert_eq!(usage.get("utun0"), InterfaceUsage::default());
+    }
+
+    #[test]
+    fn test_roll_over_new_day_keeps_month() {
+        let mut usage = usage_on(date(2026, 10, 18), 1);
+        usage.record("en0", 100);
+
+        usage.roll_over(date(2026, 10, 19));
+        usage.record("en0", 5);
+
+        assert_eq!(
+            usage.get("en0"),
+            InterfaceUsage {
+                today: 5,
+                month: 105
+            }
+        );
+    }
+
+    #[test]
+    fn test_roll_over_reset_day_clears_month() {
+        let mut usage = usage_on(date(2026, 10, 14), 15);
+        usage.record("en0", 100);
+
+        usage.roll_over(date(2026, 10, 15));
+
+        assert_eq!(usage.get("en0"), InterfaceUsage::default());
+    }
+
+    #[test]
+    fn test_roll_over_after_downtime_across_reset_day() {
+        let mut usage = usage_on(date(2026, 9, 20), 15);
+        usage.record("en0", 100);
+
+        // Restarted before the next reset day: still the same billing month.
+        usage.roll_over(date(2026, 10, 14));
+        assert_eq!(usage.get("en0").month, 100);
+
+        // Restarted after skipping a whole billing month.
+        let mut usage = usage_on(date(2026, 9, 20), 15);
+        usage.record("en0", 100);
+        usage.roll_over(date(2026, 11, 2));
+        assert_eq!(usage.get("en0").month, 0);
+    }
+
+    #[test]
+    fn test_serialize_and_restore_round_trip() {
+        let mut usage = usage_on(date(2026, 10, 18), 1);
+        usage.record("en0", 1_234);
+        usage.record("utun4", 99);
+
+        let contents = usage.serialize();
+        assert_eq!(
+            contents,
+            "# stats_provider network usage\n\
+             date\t2026-10-18\n\
+             interface\ten0\t1234\t1234\n\
+             interface\tutun4\t99\t99\n"
+        );
+
+        let mut restored = usage_on(date(2000, 1, 1), 1);
+        restored.restore(&contents).unwrap();
+        assert_eq!(restored.date, date(2026, 10, 18));
+        assert_eq!(restored.get("en0"), usage.get("en0"));
+        assert_eq!(restored.get("utun4"), usage.get("utun4"));
+    }
+
+    #[test]
+    fn test_restore_rejects_malformed_state() {
+        let mut usage = NetworkUsage::default();
+
+        assert!(usage.restore("date\tnot-a-date").is_err());
+        assert!(usage.restore("interface\ten0\tlots\t1").is_err());
+        assert!(usage.restore("something else").is_err());
+    }
+
+    #[test]
+    fn test_load_and_save_state_file() {
+        let dir =
+            std::env::temp_dir().join(format!("stats_provider_usage_test_{}", std::process::id()));
+        let path = dir.join("usage.tsv");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut usage = NetworkUsage::load(Some(path.clone()), 1).unwrap();
+        usage.record("en0", 42);
+        usage.save_if_due().unwrap();
+
+        let reloaded = NetworkUsage::load(Some(path.clone()), 1).unwrap();
+        assert_eq!(reloaded.get("en0").month, 42);
+        assert!(!path.with_extension("tmp").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_ignores_corrupt_state_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "stats_provider_usage_corrupt_{}",
+            std::process::id()
+        ));
+        let path = dir.join("usage.tsv");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            &path,
+            "date\t2026-01-01\ninterface\ten0\t1\t2\ninterface\ten1\t",
+        )
+        .unwrap();
+
+        let usage = NetworkUsage::load(Some(path.clone()), 1).unwrap();
+        assert_eq!(usage.get("en0"), InterfaceUsage::default());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,286 @@
+use std::fs;
+use std::path::Path;
+
+/// Directory of the power supply class under the sys file system root.
+const POWER_SUPPLY_DIR: &str = "class/power_supply";
+
+/// A battery as reported by the Linux power supply class, in the micro units
+/// sysfs uses.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct SupplyBattery {
+    pub(super) status: Option<String>,
+    /// Charge percentage.
+    pub(super) capacity: Option<u32>,
+    pub(super) energy_now_uwh: Option<u64>,
+    pub(super) energy_full_uwh: Option<u64>,
+    pub(super) energy_full_design_uwh: Option<u64>,
+    pub(super) power_now_uw: Option<u64>,
+    pub(super) voltage_now_uv: Option<u64>,
+    /// Temperature in tenths of a degree Celsius.
+    pub(super) temp_decicelsius: Option<i32>,
+    pub(super) cycle_count: Option<u32>,
+    pub(super) manufacturer: Option<String>,
+    pub(super) model_name: Option<String>,
+    pub(super) technology: Option<String>,
+}
+
+/// The batteries and AC adapters found under the power supply class.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct PowerSupplies {
+    pub(super) batteries: Vec<SupplyBattery>,
+    /// Whether an AC adapter is plugged in, or `None` when no adapter is
+    /// reported.
+    pub(super) ac_online: Option<bool>,
+    /// Whether a UPS is discharging, i.e. powering the machine.
+    pub(super) on_ups: bool,
+    /// Maximum power of the most powerful plugged in adapter, in µW.
+    pub(super) adapter_power_uw: Option<u64>,
+}
+
+/// Reads one attribute of a power supply, trimmed, or `None` when the driver
+/// does not provide it.
+fn read_attribute(supply: &Path, name: &str) -> Option<String> {
+    let value = fs::read_to_string(supply.join(name)).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn read_number<T: std::str::FromStr>(supply: &Path, name: &str) -> Option<T> {
+    read_attribute(supply, name)?.parse().ok()
+}
+
+/// Multiplies two values in micro units, such as a charge in µAh by a voltage
+/// in µV into an energy in µWh, or a current in µA by a voltage in µV into a
+/// power in µW.
+fn micro_product(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    Some(a? * b? / 1_000_000)
+}
+
+fn read_battery(supply: &Path) -> SupplyBattery {
+    let voltage_now_uv = read_number(supply, "voltage_now");
+    // Some drivers report charge (µAh) and current (µA) instead of energy and
+    // power; convert them using the design voltage.
+    let design_voltage_uv = read_number(supply, "voltage_min_design").or(voltage_now_uv);
+    let energy = |name: &str, charge_name: &str| {
+        read_number(supply, name)
+            .or_else(|| micro_product(read_number(supply, charge_name), design_voltage_uv))
+    };
+
+    SupplyBattery {
+        status: read_attribute(supply, "status"),
+        capacity: read_number(supply, "capacity"),
+        energy_now_uwh: energy("energy_now", "charge_now"),
+        energy_full_uwh: energy("energy_full", "charge_full"),
+        energy_full_design_uwh: energy("energy_full_design", "charge_full_design"),
+        power_now_uw: read_number::<i64>(supply, "power_now")
+            .map(i64::unsigned_abs)
+            .or_else(|| {
+                let current_ua = read_number::<i64>(supply, "current_now")?.unsigned_abs();
+                micro_product(Some(current_ua), voltage_now_uv)
+            }),
+        voltage_now_uv,
+        temp_decicelsius: read_number(supply, "temp"),
+        // Drivers without a cycle counter report zero.
+        cycle_count: read_number(supply, "cycle_count").filter(|&cycles| cycles > 0),
+        manufacturer: read_attribute(supply, "manufacturer"),
+        model_name: read_attribute(supply, "model_name"),
+        technology: read_attribute(supply, "technology"),
+    }
+}
+
+/// Reads the maximum power an adapter can supply, from the maximum voltage and
+/// current it negotiated (USB Power Delivery reports both).
+fn read_adapter_power(supply: &Path) -> Option<u64> {
+    let voltage_uv =
+        read_number(supply, "voltage_max").or_else(|| read_number(supply, "voltage_now"));
+    micro_product(read_number(supply, "current_max"), voltage_uv)
+}
+
+/// Reads every power supply under `sys_root`, ordered by name.
+///
+/// A missing power supply class reads as no batteries and no adapter, and
+/// supplies scoped to a device (such as wireless mouse batteries) are ignored.
+pub(super) fn read_power_supplies(sys_root: &Path) -> PowerSupplies {
+    let mut supplies: Vec<_> = match fs::read_dir(sys_root.join(POWER_SUPPLY_DIR)) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).collect(),
+        Err(_) => return PowerSupplies::default(),
+    };
+    supplies.sort_by_key(|entry| entry.file_name());
+
+    let mut power_supplies = PowerSupplies::default();
+    for supply in supplies.iter().map(|entry| entry.path()) {
+        if read_attribute(&supply, "scope").as_deref() == Some("Device") {
+            continue;
+        }
+
+        match read_attribute(&supply, "type").as_deref() {
+            Some("Battery") => power_supplies.batteries.push(read_battery(&supply)),
+            Some("Mains" | "USB") => {
+                let online = read_number::<u32>(&supply, "online").is_some_and(|v| v > 0);
+                power_supplies.ac_online = Some(power_supplies.ac_online == Some(true) || online);
+                if online {
+                    let power = read_adapter_power(&supply);
+                    power_supplies.adapter_power_uw = power_supplies.adapter_power_uw.max(power);
+                }
+            }
+            Some("UPS") if read_attribute(&supply, "status").as_deref() == Some("Discharging") => {
+                power_supplies.on_ups = true;
+            }
+            _ => {}
+        }
+    }
+    power_supplies
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Creates a sys root containing the given power supplies, each a list of
+    /// `(attribute, value)` pairs.
+    pub(in crate::stats) fn fixture(name: &str, supplies: &[(&str, &[(&str, &str)])]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "stats_provider_power_supply_{name}_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        for (supply, attributes) in supplies {
+            let dir = root.join(POWER_SUPPLY_DIR).join(supply);
+            fs::create_dir_all(&dir).unwrap();
+            for (attribute, value) in *attributes {
+                fs::write(dir.join(attribute), format!("{value}\n")).unwrap();
+            }
+        }
+        root
+    }
+
+    pub(in crate::stats) const LAPTOP: &[(&str, &[(&str, &str)])] = &[
+        ("AC", &[("type", "Mains"), ("online", "0")]),
+        (
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "80"),
+                ("energy_now", "40000000"),
+                ("energy_full", "50000000"),
+                ("energy_full_design", "57000000"),
+                ("power_now", "12500000"),
+                ("voltage_now", "12456000"),
+                ("cycle_count", "312"),
+                ("manufacturer", "SMP"),
+                ("model_name", "5B10W13975"),
+                ("technology", "Li-poly"),
+            ],
+        ),
+        (
+            "hidpp_battery_0",
+            &[("type", "Battery"), ("scope", "Device"), ("capacity", "55")],
+        ),
+    ];
+
+    #[test]
+    fn test_read_power_supplies() {
+        let root = fixture("laptop", LAPTOP);
+
+        let supplies = read_power_supplies(&root);
+
+        assert_eq!(supplies.ac_online, Some(false));
+        assert!(!supplies.on_ups);
+        assert_eq!(supplies.adapter_power_uw, None);
+        assert_eq!(
+            supplies.batteries,
+            vec![SupplyBattery {
+                status: Some("Discharging".to_string()),
+                capacity: Some(80),
+                energy_now_uwh: Some(40_000_000),
+                energy_full_uwh: Some(50_000_000),
+                energy_full_design_uwh: Some(57_000_000),
+                power_now_uw: Some(12_500_000),
+                voltage_now_uv: Some(12_456_000),
+                temp_decicelsius: None,
+                cycle_count: Some(312),
+                manufacturer: Some("SMP".to_string()),
+                model_name: Some("5B10W13975".to_string()),
+                technology: Some("Li-poly".to_string()),
+            }]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_read_power_supplies_charge_based_battery() {
+        let root = fixture(
+            "charge",
+            &[
+                ("ADP1", &[("type", "Mains"), ("online", "1")]),
+                (
+                    "ucsi-source-psy-USBC000:002",
+                    &[
+                        ("type", "USB"),
+                        ("online", "1"),
+                        ("voltage_max", "20000000"),
+                        ("current_max", "3250000"),
+                    ],
+                ),
+                (
+                    "ucsi-source-psy-USBC000:001",
+                    &[("type", "USB"), ("online", "0")],
+                ),
+                (
+                    "BAT1",
+                    &[
+                        ("type", "Battery"),
+                        ("status", "Charging"),
+                        ("charge_now", "2000000"),
+                        ("charge_full", "4000000"),
+                        ("current_now", "-1500000"),
+                        ("voltage_min_design", "11000000"),
+                        ("voltage_now", "12000000"),
+                        ("cycle_count", "0"),
+                    ],
+                ),
+            ],
+        );
+
+        let supplies = read_power_supplies(&root);
+
+        assert_eq!(supplies.ac_online, Some(true));
+        assert_eq!(supplies.adapter_power_uw, Some(65_000_000));
+        let battery = &supplies.batteries[0];
+        assert_eq!(battery.energy_now_uwh, Some(22_000_000));
+        assert_eq!(battery.energy_full_uwh, Some(44_000_000));
+        assert_eq!(battery.energy_full_design_uwh, None);
+        assert_eq!(battery.power_now_uw, Some(18_000_000));
+        assert_eq!(battery.cycle_count, None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_read_power_supplies_missing_class() {
+        let supplies = read_power_supplies(Path::new("/nonexistent/sys"));
+
+        assert_eq!(supplies, PowerSupplies::default());
+    }
+
+    #[test]
+    fn test_read_power_supplies_on_ups() {
+        let root = fixture(
+            "ups",
+            &[
+                ("AC", &[("type", "Mains"), ("online", "0")]),
+                ("ups", &[("type", "UPS"), ("status", "Discharging")]),
+            ],
+        );
+
+        let supplies = read_power_supplies(&root);
+
+        assert!(supplies.on_ups);
+        assert!(supplies.batteries.is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
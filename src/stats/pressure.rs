@@ -0,0 +1,164 @@
+use super::unit;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Averaging windows reported by PSI, in seconds.
+const WINDOWS: &[(&str, &str)] = &[("avg10", "10"), ("avg60", "60"), ("avg300", "300")];
+
+/// Stall averages of one PSI line, in percent, ordered like `WINDOWS`.
+type StallAverages = [f32; 3];
+
+/// Pressure stall information of one resource: the share of time some tasks,
+/// or all non-idle tasks, were stalled waiting on it.
+#[derive(Debug, Default, PartialEq)]
+struct Pressure {
+    some: Option<StallAverages>,
+    full: Option<StallAverages>,
+}
+
+/// Parses the contents of a `/proc/pressure/<resource>` file, e.g.
+/// `some avg10=0.12 avg60=0.05 avg300=0.01 total=123456`.
+fn parse_pressure(contents: &str) -> Pressure {
+    let mut pressure = Pressure::default();
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        let target = match fields.next() {
+            Some("some") => &mut pressure.some,
+            Some("full") => &mut pressure.full,
+            _ => continue,
+        };
+
+        let mut averages = [None; 3];
+        for (key, value) in fields.filter_map(|field| field.split_once('=')) {
+            if let Some(index) = WINDOWS.iter().position(|&(window, _)| window == key) {
+                averages[index] = value.parse::<f32>().ok();
+            }
+        }
+        if let [Some(avg10), Some(avg60), Some(avg300)] = averages {
+            *target = Some([avg10, avg60, avg300]);
+        }
+    }
+    pressure
+}
+
+/// Reads the pressure of `resource` (`cpu`, `io` or `memory`) under
+/// `proc_root`, or `None` when the kernel does not report it.
+fn read_pressure(proc_root: &Path, resource: &str) -> Option<Pressure> {
+    fs::read_to_string(proc_root.join("pressure").join(resource))
+        .ok()
+        .map(|contents| parse_pressure(&contents))
+}
+
+fn write_averages(buf: &mut String, key: &str, averages: Option<StallAverages>, no_units: bool) {
+    let unit = unit(no_units, "%");
+    for (index, (_, window)) in WINDOWS.iter().enumerate() {
+        match averages {
+            Some(averages) => {
+                let _ = write!(buf, "{key}_{window}=\"{:.2}{unit}\" ", averages[index]);
+            }
+            None => {
+                let _ = write!(buf, "{key}_{window}=\"N/A\" ");
+            }
+        }
+    }
+}
+
+/// Writes the Linux pressure stall information of the resources in `flags`
+/// as `PRESSURE_<RESOURCE>_<SOME|FULL>_<WINDOW>`, in percent. Resources the
+/// kernel does not report (including on every other OS) are omitted, and
+/// lines it does not report report `N/A`.
+pub fn get_pressure_stats(flags: &[&str], proc_root: &Path, no_units: bool, buf: &mut String) {
+    for &flag in flags {
+        let Some(pressure) = read_pressure(proc_root, flag) else {
+            continue;
+        };
+        let resource = flag.to_ascii_uppercase();
+        write_averages(
+            buf,
+            &format!("PRESSURE_{resource}_SOME"),
+            pressure.some,
+            no_units,
+        );
+        write_averages(
+            buf,
+            &format!("PRESSURE_{resource}_FULL"),
+            pressure.full,
+            no_units,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMORY_PRESSURE: &str = "\
+some avg10=1.53 avg60=0.87 avg300=0.25 total=4519316
+full avg10=0.40 avg60=0.12 avg300=0.03 total=1902731
+";
+
+    #[test]
+    fn test_parse_pressure() {
+        assert_eq!(
+            parse_pressure(MEMORY_PRESSURE),
+            Pressure {
+                some: Some([1.53, 0.87, 0.25]),
+                full: Some([0.40, 0.12, 0.03]),
+            }
+        );
+        // Older kernels only report `some` for the CPU.
+        assert_eq!(
+            parse_pressure("some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n"),
+            Pressure {
+                some: Some([0.0, 0.0, 0.0]),
+                full: None,
+            }
+        );
+        assert_eq!(parse_pressure("some avg10=x\n"), Pressure::default());
+    }
+
+    #[test]
+    fn test_get_pressure_stats_reads_proc_root() {
+        let proc_root =
+            std::env::temp_dir().join(format!("stats_provider_pressure_{}", std::process::id()));
+        fs::create_dir_all(proc_root.join("pressure")).unwrap();
+        fs::write(proc_root.join("pressure/memory"), MEMORY_PRESSURE).unwrap();
+        let mut buf = String::new();
+
+        get_pressure_stats(&["memory", "io"], &proc_root, false, &mut buf);
+
+        assert_eq!(
+            buf,
+            "PRESSURE_MEMORY_SOME_10=\"1.53%\" PRESSURE_MEMORY_SOME_60=\"0.87%\" \
+             PRESSURE_MEMORY_SOME_300=\"0.25%\" PRESSURE_MEMORY_FULL_10=\"0.40%\" \
+             PRESSURE_MEMORY_FULL_60=\"0.12%\" PRESSURE_MEMORY_FULL_300=\"0.03%\" "
+        );
+
+        fs::remove_dir_all(&proc_root).unwrap();
+    }
+
+    #[test]
+    fn test_get_pressure_stats_without_units() {
+        let proc_root = std::env::temp_dir().join(format!(
+            "stats_provider_pressure_no_units_{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(proc_root.join("pressure")).unwrap();
+        fs::write(
+            proc_root.join("pressure/cpu"),
+            "some avg10=12.50 avg60=3.00 avg300=1.25 total=99\n",
+        )
+        .unwrap();
+        let mut buf = String::new();
+
+        get_pressure_stats(&["cpu"], &proc_root, true, &mut buf);
+
+        assert!(buf.starts_with(
+            "PRESSURE_CPU_SOME_10=\"12.50\" PRESSURE_CPU_SOME_60=\"3.00\" PRESSURE_CPU_SOME_300=\"1.25\" "
+        ));
+        assert!(buf.ends_with("PRESSURE_CPU_FULL_300=\"N/A\" "));
+
+        fs::remove_dir_all(&proc_root).unwrap();
+    }
+}
@@ -0,0 +1,363 @@
+use super::{PERCENT, key_fragment, strip_quotes, unique_keys, unit};
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt::Write;
+use sysinfo::{Process, ProcessStatus, System};
+
+/// How a watched process is matched against process names.
+#[derive(Debug)]
+enum ProcessMatcher {
+    Exact(String),
+    Regex(Regex),
+}
+
+/// A process requested with `--watch-process`, with the key it is reported under.
+#[derive(Debug)]
+pub struct ProcessWatch {
+    key: String,
+    matcher: ProcessMatcher,
+}
+
+impl ProcessWatch {
+    /// Parses a watch spec: an exact process name, or a regex wrapped in slashes
+    /// (e.g. `/^postgres/`).
+    pub fn parse(spec: &str) -> Result<Self> {
+        let matcher = match spec
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+        {
+            Some(pattern) => ProcessMatcher::Regex(
+                Regex::new(pattern)
+                    .with_context(|| format!("Invalid process regex '{pattern}'"))?,
+            ),
+            None => ProcessMatcher::Exact(spec.to_string()),
+        };
+
+        let key = key_fragment(spec);
+        if key.is_empty() {
+            bail!("Process watch '{spec}' does not contain any alphanumeric characters");
+        }
+
+        Ok(Self { key, matcher })
+    }
+
+    /// Parses every watch spec, suffixing keys that collide (e.g. `foo-bar`
+    /// and `foo_bar`) so each watch keeps its own values.
+    pub fn parse_all(specs: &[String]) -> Result<Vec<Self>> {
+        let mut watches = specs
+            .iter()
+            .map(|spec| Self::parse(spec))
+            .collect::<Result<Vec<_>>>()?;
+        let keys = unique_keys(watches.iter().map(|watch| watch.key.clone()));
+        for (watch, key) in watches.iter_mut().zip(keys) {
+            watch.key = key;
+        }
+        Ok(watches)
+    }
+
+    fn matches(&self, process: &Process) -> bool {
+        let name = process.name().to_string_lossy();
+        match &self.matcher {
+            ProcessMatcher::Exact(expected) => name == expected.as_str(),
+            ProcessMatcher::Regex(regex) => regex.is_match(&name),
+        }
+    }
+}
+
+/// Returns every process, skipping the threads Linux lists alongside them.
+fn real_processes(s: &System) -> Vec<&Process> {
+    s.processes()
+        .values()
+        .filter(|process| process.thread_kind().is_none())
+        .collect()
+}
+
+/// Returns the number of threads of `process`, including its main thread, or
+/// `None` when it cannot be inspected.
+#[cfg(target_os = "macos")]
+fn thread_count(process: &Process) -> Option<usize> {
+    let mut info: libc::proc_taskinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_taskinfo>() as libc::c_int;
+    // SAFETY: `info` is a writable `proc_taskinfo` of exactly `size` bytes.
+    let written = unsafe {
+        libc::proc_pidinfo(
+            process.pid().as_u32() as libc::c_int,
+            libc::PROC_PIDTASKINFO,
+            0,
+            (&raw mut info).cast(),
+            size,
+        )
+    };
+    (written == size).then_some(info.pti_threadnum as usize)
+}
+
+/// Returns the number of threads of `process`, including its main thread, or
+/// `None` when its thread list was not refreshed.
+#[cfg(not(target_os = "macos"))]
+fn thread_count(process: &Process) -> Option<usize> {
+    // Each thread list excludes the main thread.
+    process.tasks().map(|tasks| tasks.len() + 1)
+}
+
+/// Returns the share of total memory used by `process` as a percentage.
+fn memory_percentage(process: &Process, total_memory: u64) -> f32 {
+    if total_memory > 0 {
+        process.memory() as f32 / total_memory as f32 * PERCENT
+    } else {
+        0.0
+    }
+}
+
+/// Returns the process name, safe to embed in a sketchybar value.
+fn process_name(process: &Process) -> String {
+    strip_quotes(&process.name().to_string_lossy())
+}
+
+/// Returns the `count` heaviest processes ordered by `weight`, descending.
+fn top_processes<'a>(
+    processes: &[&'a Process],
+    count: usize,
+    weight: impl Fn(&Process) -> f32,
+) -> Vec<&'a Process> {
+    let mut sorted = processes.to_vec();
+    sorted.sort_by(|a, b| weight(b).partial_cmp(&weight(a)).unwrap_or(Ordering::Equal));
+    sorted.truncate(count);
+    sorted
+}
+
+pub fn get_process_stats(
+    s: &System,
+    flags: &[&str],
+    top_count: usize,
+    no_units: bool,
+    buf: &mut String,
+) {
+    let processes = real_processes(s);
+
+    for &flag in flags {
+        match flag {
+            "count" => {
+                let _ = write!(buf, "PROC_COUNT=\"{}\" ", processes.len());
+            }
+            "running" => {
+                let running = processes
+                    .iter()
+                    .filter(|process| process.status() == ProcessStatus::Run)
+                    .count();
+                let _ = write!(buf, "PROC_RUNNING=\"{running}\" ");
+            }
+            "threads" => {
+                // Processes that cannot be inspected, such as those of other
+                // users on macOS, are left out of the count.
+                let counts: Vec<usize> = processes
+                    .iter()
+                    .filter_map(|process| thread_count(process))
+                    .collect();
+                if counts.is_empty() {
+                    let _ = write!(buf, "PROC_THREADS=\"N/A\" ");
+                } else {
+                    let threads: usize = counts.iter().sum();
+                    let _ = write!(buf, "PROC_THREADS=\"{threads}\" ");
+                }
+            }
+            "top_cpu" => {
+                let unit = unit(no_units, "%");
+                for (i, process) in top_processes(&processes, top_count, Process::cpu_usage)
+                    .iter()
+                    .enumerate()
+                {
+                    let index = i + 1;
+                    let _ = write!(
+                        buf,
+                        "PROC_TOP_CPU_{index}_NAME=\"{}\" PROC_TOP_CPU_{index}_PID=\"{}\" PROC_TOP_CPU_{index}_CPU=\"{:.1}{unit}\" ",
+                        process_name(process),
+                        process.pid(),
+                        process.cpu_usage()
+                    );
+                }
+            }
+            "top_mem" => {
+                let total_memory = s.total_memory();
+                let mem_percentage = |process: &Process| memory_percentage(process, total_memory);
+                let unit = unit(no_units, "%");
+                for (i, process) in top_processes(&processes, top_count, mem_percentage)
+                    .iter()
+                    .enumerate()
+                {
+                    let index = i + 1;
+                    let _ = write!(
+                        buf,
+                        "PROC_TOP_MEM_{index}_NAME=\"{}\" PROC_TOP_MEM_{index}_PID=\"{}\" PROC_TOP_MEM_{index}_MEM=\"{:.1}{unit}\" ",
+                        process_name(process),
+                        process.pid(),
+                        mem_percentage(process)
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn get_watched_process_stats(
+    s: &System,
+    watches: &[ProcessWatch],
+    no_units: bool,
+    buf: &mut String,
+) {
+    let processes = real_processes(s);
+    let total_memory = s.total_memory();
+    let unit = unit(no_units, "%");
+
+    for watch in watches {
+        let (count, cpu, mem) = processes
+            .iter()
+            .filter(|process| watch.matches(process))
+            .fold((0, 0.0, 0.0), |(count, cpu, mem), process| {
+                (
+                    count + 1,
+                    cpu + process.cpu_usage(),
+                    mem + memory_percentage(process, total_memory),
+                )
+            });
+        let key = &watch.key;
+        let _ = write!(
+            buf,
+            "PROC_{key}_RUNNING=\"{}\" PROC_{key}_COUNT=\"{count}\" PROC_{key}_CPU=\"{cpu:.1}{unit}\" PROC_{key}_MEM=\"{mem:.1}{unit}\" ",
+            count > 0
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::build_refresh_kind;
+
+    fn process_system() -> System {
+        let mut s = System::new_with_specifics(build_refresh_kind(true, true));
+        s.refresh_specifics(build_refresh_kind(true, true));
+        s
+    }
+
+    #[test]
+    fn test_get_process_stats_all_flags_emit_expected_keys() {
+        use crate::cli;
+
+        let s = process_system();
+        let mut buf = String::new();
+
+        get_process_stats(&s, cli::ALL_PROCESS_FLAGS, 3, false, &mut buf);
+
+        assert!(buf.contains("PROC_COUNT="));
+        assert!(buf.contains("PROC_RUNNING="));
+        assert!(buf.contains("PROC_THREADS="));
+        assert!(buf.contains("PROC_TOP_CPU_1_NAME="));
+        assert!(buf.contains("PROC_TOP_CPU_1_PID="));
+        assert!(buf.contains("PROC_TOP_CPU_1_CPU="));
+        assert!(buf.contains("PROC_TOP_MEM_1_NAME="));
+        assert!(buf.contains("PROC_TOP_MEM_1_PID="));
+        assert!(buf.contains("PROC_TOP_MEM_1_MEM="));
+        assert!(buf.contains("%"));
+    }
+
+    #[test]
+    fn test_get_process_stats_top_count_limits_entries() {
+        let s = process_system();
+        let mut buf = String::new();
+
+        get_process_stats(&s, &["top_cpu"], 1, true, &mut buf);
+
+        assert!(buf.contains("PROC_TOP_CPU_1_NAME="));
+        assert!(!buf.contains("PROC_TOP_CPU_2_NAME="));
+        assert!(!buf.contains("%"));
+    }
+
+    #[test]
+    fn test_get_process_stats_count_includes_current_process() {
+        let s = process_system();
+        let mut buf = String::new();
+
+        get_process_stats(&s, &["count"], 1, false, &mut buf);
+
+        let value = buf
+            .strip_prefix("PROC_COUNT=\"")
+            .and_then(|rest| rest.split('"').next())
+            .and_then(|count| count.parse::<usize>().ok())
+            .expect("numeric process count");
+        assert!(value >= 1, "at least the test process must be counted");
+    }
+
+    #[test]
+    fn test_get_process_stats_threads_counts_current_process() {
+        let s = process_system();
+        let mut buf = String::new();
+
+        get_process_stats(&s, &["threads"], 1, false, &mut buf);
+
+        let value = buf
+            .strip_prefix("PROC_THREADS=\"")
+            .and_then(|rest| rest.split('"').next())
+            .and_then(|threads| threads.parse::<usize>().ok())
+            .expect("numeric thread count");
+        assert!(value >= 1, "at least the test thread must be counted");
+    }
+
+    #[test]
+    fn test_get_process_stats_empty_flags() {
+        let s = process_system();
+        let mut buf = String::new();
+
+        get_process_stats(&s, &[], 3, false, &mut buf);
+
+        assert_eq!(buf, "");
+    }
+
+    #[test]
+    fn test_process_watch_parse() {
+        let exact = ProcessWatch::parse("postgres").unwrap();
+        assert_eq!(exact.key, "POSTGRES");
+        assert!(matches!(exact.matcher, ProcessMatcher::Exact(ref name) if name == "postgres"));
+
+        let regex = ProcessWatch::parse("/^post(gres|master)$/").unwrap();
+        assert_eq!(regex.key, "POST_GRES_MASTER");
+        assert!(matches!(regex.matcher, ProcessMatcher::Regex(_)));
+
+        assert!(ProcessWatch::parse("/(unclosed/").is_err());
+        assert!(ProcessWatch::parse("//").is_err());
+
+        let watches =
+            ProcessWatch::parse_all(&["foo-bar".to_string(), "foo_bar".to_string()]).unwrap();
+        assert_eq!(watches[0].key, "FOO_BAR");
+        assert_eq!(watches[1].key, "FOO_BAR_2");
+    }
+
+    #[test]
+    fn test_get_watched_process_stats_reports_current_process() {
+        let s = process_system();
+        let current = s
+            .process(sysinfo::get_current_pid().unwrap())
+            .expect("current process is listed");
+        let name = current.name().to_string_lossy().to_string();
+        let watches = [
+            ProcessWatch::parse(&format!("/^{}$/", regex::escape(&name))).unwrap(),
+            ProcessWatch::parse("definitely-not-a-process-xyz").unwrap(),
+        ];
+        let mut buf = String::new();
+
+        get_watched_process_stats(&s, &watches, false, &mut buf);
+
+        let key = &watches[0].key;
+        assert!(
+            buf.contains(&format!("PROC_{key}_RUNNING=\"true\"")),
+            "{buf}"
+        );
+        assert!(!buf.contains(&format!("PROC_{key}_COUNT=\"0\"")), "{buf}");
+        assert!(buf.contains(&format!("PROC_{key}_CPU=")), "{buf}");
+        assert!(buf.contains(&format!("PROC_{key}_MEM=")), "{buf}");
+        assert!(buf.contains("PROC_DEFINITELY_NOT_A_PROCESS_XYZ_RUNNING=\"false\""));
+        assert!(buf.contains("PROC_DEFINITELY_NOT_A_PROCESS_XYZ_COUNT=\"0\""));
+        assert!(buf.contains("PROC_DEFINITELY_NOT_A_PROCESS_XYZ_CPU=\"0.0%\""));
+    }
+}
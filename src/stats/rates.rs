@@ -0,0 +1,72 @@
+//! Rates of cumulative counters between two ticks.
+
+/// Converts a counter delta and the elapsed time into a rate per second.
+pub(super) fn rate_per_sec(delta: u64, elapsed_secs: f64) -> f64 {
+    if elapsed_secs <= 0.0 {
+        return 0.0;
+    }
+    delta as f64 / elapsed_secs
+}
+
+/// Computes the per-second rates of a pair of cumulative counters (e.g. rx/tx
+/// bytes or swapped in/out pages) from their previous and current totals.
+///
+/// Returns `(0.0, 0.0)` when there is no previous baseline (first sighting)
+/// or when either counter wrapped around.
+pub(super) fn counter_rates(
+    prev_first: Option<u64>,
+    prev_second: Option<u64>,
+    first_total: u64,
+    second_total: u64,
+    elapsed_secs: f64,
+) -> (f64, f64) {
+    let (Some(prev_first), Some(prev_second)) = (prev_first, prev_second) else {
+        return (0.0, 0.0);
+    };
+
+    if first_total < prev_first || second_total < prev_second {
+        return (0.0, 0.0);
+    }
+
+    (
+        rate_per_sec(first_total - prev_first, elapsed_secs),
+        rate_per_sec(second_total - prev_second, elapsed_secs),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_per_sec_divides_by_elapsed() {
+        assert_eq!(rate_per_sec(2048, 2.0), 1024.0);
+        assert_eq!(rate_per_sec(1000, 0.5), 2000.0);
+    }
+
+    #[test]
+    fn test_rate_per_sec_zero_elapsed_returns_zero() {
+        assert_eq!(rate_per_sec(1024, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_counter_rates_first_sighting_returns_zero() {
+        assert_eq!(counter_rates(None, None, 1000, 2000, 1.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_counter_rates_counter_wrap_returns_zero() {
+        assert_eq!(
+            counter_rates(Some(1000), Some(2000), 500, 2500, 1.0),
+            (0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_counter_rates_normal_delta() {
+        assert_eq!(
+            counter_rates(Some(2048), Some(4096), 4096, 6144, 1.0),
+            (2048.0, 2048.0)
+        );
+    }
+}
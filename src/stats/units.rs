@@ -0,0 +1,323 @@
+use super::{BYTES_PER_GB, BYTES_PER_GB_SI, BYTES_PER_KB, BYTES_PER_KB_SI, unit};
+use crate::cli::UnitSystem;
+
+/// Unit labels used by `--auto-units` for 1024-based scaling.
+const IEC_UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// Unit labels used by `--auto-units` for 1000-based scaling.
+const SI_UNITS: &[&str] = &["B", "kB", "MB", "GB", "TB"];
+
+/// Unit labels used for bit rates, which are always 1000-based.
+const BIT_UNITS: &[&str] = &["bit", "Kbit", "Mbit", "Gbit", "Tbit"];
+
+/// Bits in a byte.
+const BITS_PER_BYTE: f64 = 8.0;
+
+/// Widest integer part of an auto-scaled value (e.g. `1023`) before it moves
+/// up to the next unit.
+const AUTO_INTEGER_DIGITS: usize = 4;
+
+/// Converts a rate in bytes per second into whole `KiB/s`.
+pub fn kib_per_sec(bytes_per_sec: f64) -> u64 {
+    (bytes_per_sec / BYTES_PER_KB as f64).round() as u64
+}
+
+/// How byte sizes and transfer rates are rendered.
+///
+/// Without a `unit_system`, sizes keep the historical 1024-based `GB` label
+/// and rates are reported in `KiB/s`.
+#[derive(Clone, Copy, Debug)]
+pub struct FormatOptions {
+    pub no_units: bool,
+    pub auto_units: bool,
+    pub unit_system: Option<UnitSystem>,
+    pub precision: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            no_units: false,
+            auto_units: false,
+            unit_system: None,
+            precision: 1,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Formats a byte count in gigabytes with one decimal, or auto-scaled when
+    /// `auto_units` is set.
+    pub fn size(&self, bytes: u64) -> String {
+        if self.auto_units {
+            return self.auto_scaled(bytes as f64, "");
+        }
+
+        let (divisor, label) = match self.unit_system {
+            None => (BYTES_PER_GB, "GB"),
+            Some(UnitSystem::Iec) => (BYTES_PER_GB, "GiB"),
+            Some(UnitSystem::Si) => (BYTES_PER_GB_SI, "GB"),
+        };
+        let unit = unit(self.no_units, label);
+        format!("{:.1}{unit}", bytes as f32 / divisor)
+    }
+
+    /// Formats a rate in bytes per second as whole kilobytes per second, or
+    /// auto-scaled when `auto_units` is set.
+    pub fn rate(&self, bytes_per_sec: f64) -> String {
+        if self.auto_units {
+            return self.auto_scaled(bytes_per_sec, "/s");
+        }
+
+        match self.unit_system {
+            None | Some(UnitSystem::Iec) => {
+                let unit = unit(self.no_units, "KiB/s");
+                format!("{}{unit}", kib_per_sec(bytes_per_sec))
+            }
+            Some(UnitSystem::Si) => {
+                let unit = unit(self.no_units, "kB/s");
+                format!(
+                    "{}{unit}",
+                    (bytes_per_sec / BYTES_PER_KB_SI as f64).round() as u64
+                )
+            }
+        }
+    }
+
+    /// Formats a rate in bytes per second as whole kilobits per second, or
+    /// auto-scaled through Kbit/s, Mbit/s and Gbit/s when `auto_units` is set.
+    ///
+    /// Bit rates always use 1000-based units, regardless of `unit_system`.
+    pub fn bit_rate(&self, bytes_per_sec: f64) -> String {
+        let bits_per_sec = bytes_per_sec * BITS_PER_BYTE;
+        if self.auto_units {
+            return self.scaled(bits_per_sec, BYTES_PER_KB_SI as f64, BIT_UNITS, "/s");
+        }
+
+        let unit = unit(self.no_units, "Kbit/s");
+        format!(
+            "{}{unit}",
+            (bits_per_sec / BYTES_PER_KB_SI as f64).round() as u64
+        )
+    }
+
+    /// Scales a byte `value` using the units of the configured unit system.
+    fn auto_scaled(&self, value: f64, per: &str) -> String {
+        match self.unit_system {
+            Some(UnitSystem::Si) => self.scaled(value, BYTES_PER_KB_SI as f64, SI_UNITS, per),
+            None | Some(UnitSystem::Iec) => self.scaled(value, BYTES_PER_KB as f64, IEC_UNITS, per),
+        }
+    }
+
+    /// Scales `value` to the largest unit that keeps it below `base`, padded
+    /// on the left to a fixed width so labels do not shift as values change.
+    fn scaled(&self, value: f64, base: f64, units: &[&str], per: &str) -> String {
+        let precision = self.precision;
+        let factor = 10f64.powi(precision as i32);
+
+        let mut scaled = value.max(0.0);
+        let mut index = 0;
+        // Compare the rounded value so e.g. 1023.96KiB becomes 1.0MiB, not 1024.0KiB.
+        while index + 1 < units.len() && (scaled * factor).round() / factor >= base {
+            scaled /= base;
+            index += 1;
+        }
+
+        // `validate_cli` rejects `--auto-units` together with `--no-units`.
+        debug_assert!(!self.no_units, "auto-scaled values always carry a unit");
+        let width = AUTO_INTEGER_DIGITS
+            + if precision > 0 { precision + 1 } else { 0 }
+            + units.iter().map(|unit| unit.len()).max().unwrap_or(0)
+            + per.len();
+        let value = format!("{scaled:.precision$}{}{per}", units[index]);
+        format!("{value:>width$}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto(si: bool, precision: usize) -> FormatOptions {
+        FormatOptions {
+            no_units: false,
+            auto_units: true,
+            unit_system: si.then_some(UnitSystem::Si),
+            precision,
+        }
+    }
+
+    fn fixed(unit_system: Option<UnitSystem>) -> FormatOptions {
+        FormatOptions {
+            unit_system,
+            ..FormatOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_kib_per_sec_rounds_to_nearest() {
+        assert_eq!(kib_per_sec(3072.0), 3);
+        assert_eq!(kib_per_sec(1500.0), 1);
+        assert_eq!(kib_per_sec(500.0), 0);
+        // 2000 B/s is 1.95 KiB/s, which rounds up rather than truncating.
+        assert_eq!(kib_per_sec(2000.0), 2);
+    }
+
+    #[test]
+    fn test_default_size_is_gb_with_one_decimal() {
+        let format = FormatOptions::default();
+
+        assert_eq!(format.size(1_610_612_736), "1.5GB");
+        assert_eq!(format.size(536_870_912), "0.5GB");
+    }
+
+    #[test]
+    fn test_default_rate_is_whole_kib_per_sec() {
+        let format = FormatOptions::default();
+
+        assert_eq!(format.rate(41_943_040.0), "40960KiB/s");
+        assert_eq!(format.rate(0.0), "0KiB/s");
+    }
+
+    #[test]
+    fn test_default_no_units() {
+        let format = FormatOptions {
+            no_units: true,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(format.size(1_610_612_736), "1.5");
+        assert_eq!(format.rate(2048.0), "2");
+    }
+
+    #[test]
+    fn test_auto_units_iec_picks_unit_per_value() {
+        let format = auto(false, 1);
+
+        assert_eq!(format.size(512).trim(), "512.0B");
+        assert_eq!(format.size(500 * 1024 * 1024).trim(), "500.0MiB");
+        assert_eq!(format.size(3 * 1024 * 1024 * 1024).trim(), "3.0GiB");
+        assert_eq!(format.size(2 * 1024u64.pow(4)).trim(), "2.0TiB");
+        assert_eq!(format.rate(40.0 * 1024.0 * 1024.0).trim(), "40.0MiB/s");
+    }
+
+    #[test]
+    fn test_auto_units_si_picks_unit_per_value() {
+        let format = auto(true, 1);
+
+        assert_eq!(format.size(500_000_000).trim(), "500.0MB");
+        assert_eq!(format.size(1_500).trim(), "1.5kB");
+        assert_eq!(format.rate(2_000_000.0).trim(), "2.0MB/s");
+    }
+
+    #[test]
+    fn test_unit_system_fixed_sizes() {
+        let gib = 1_073_741_824;
+
+        assert_eq!(fixed(None).size(gib), "1.0GB");
+        assert_eq!(fixed(Some(UnitSystem::Iec)).size(gib), "1.0GiB");
+        assert_eq!(fixed(Some(UnitSystem::Si)).size(gib), "1.1GB");
+        assert_eq!(fixed(Some(UnitSystem::Si)).size(2_000_000_000), "2.0GB");
+    }
+
+    #[test]
+    fn test_unit_system_fixed_rates() {
+        assert_eq!(fixed(None).rate(10_240.0), "10KiB/s");
+        assert_eq!(fixed(Some(UnitSystem::Iec)).rate(10_240.0), "10KiB/s");
+        assert_eq!(fixed(Some(UnitSystem::Si)).rate(10_240.0), "10kB/s");
+        assert_eq!(fixed(Some(UnitSystem::Si)).rate(1_900_000.0), "1900kB/s");
+        assert_eq!(fixed(Some(UnitSystem::Iec)).rate(1_900_000.0), "1855KiB/s");
+    }
+
+    #[test]
+    fn test_unit_system_auto_scaled() {
+        let iec = FormatOptions {
+            unit_system: Some(UnitSystem::Iec),
+            ..auto(false, 1)
+        };
+
+        assert_eq!(iec.size(1_500_000_000).trim(), "1.4GiB");
+        assert_eq!(auto(true, 1).size(1_500_000_000).trim(), "1.5GB");
+        assert_eq!(auto(false, 1).size(1_500_000_000).trim(), "1.4GiB");
+        assert_eq!(iec.rate(1_500_000.0).trim(), "1.4MiB/s");
+        assert_eq!(auto(true, 1).rate(1_500_000.0).trim(), "1.5MB/s");
+    }
+
+    #[test]
+    fn test_unit_system_no_units() {
+        let si = FormatOptions {
+            no_units: true,
+            ..fixed(Some(UnitSystem::Si))
+        };
+
+        assert_eq!(si.size(2_000_000_000), "2.0");
+        assert_eq!(si.rate(12_000.0), "12");
+    }
+
+    #[test]
+    fn test_bit_rate_fixed_is_whole_kbit_per_sec() {
+        let format = FormatOptions::default();
+
+        // 125 000 B/s is exactly 1 Mbit/s.
+        assert_eq!(format.bit_rate(125_000.0), "1000Kbit/s");
+        assert_eq!(format.bit_rate(0.0), "0Kbit/s");
+        assert_eq!(fixed(Some(UnitSystem::Iec)).bit_rate(1_000.0), "8Kbit/s");
+    }
+
+    #[test]
+    fn test_bit_rate_auto_scaled() {
+        let format = auto(false, 1);
+
+        assert_eq!(format.bit_rate(100.0).trim(), "800.0bit/s");
+        assert_eq!(format.bit_rate(12_500.0).trim(), "100.0Kbit/s");
+        assert_eq!(format.bit_rate(125_000.0).trim(), "1.0Mbit/s");
+        assert_eq!(format.bit_rate(62_500_000.0).trim(), "500.0Mbit/s");
+        assert_eq!(auto(true, 2).bit_rate(125_000_000.0).trim(), "1.00Gbit/s");
+    }
+
+    #[test]
+    fn test_bit_rate_auto_width_is_stable() {
+        let format = auto(false, 1);
+
+        assert_eq!(
+            format.bit_rate(1.0).len(),
+            format.bit_rate(125_000_000.0).len()
+        );
+        assert_eq!(format.bit_rate(1.0), "    8.0bit/s");
+    }
+
+    #[test]
+    fn test_bit_rate_no_units() {
+        let format = FormatOptions {
+            no_units: true,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(format.bit_rate(125_000.0), "1000");
+    }
+
+    #[test]
+    fn test_auto_units_rounding_moves_to_next_unit() {
+        assert_eq!(auto(false, 1).size(1024 * 1024 - 20).trim(), "1.0MiB");
+        assert_eq!(auto(false, 0).size(1023).trim(), "1023B");
+    }
+
+    #[test]
+    fn test_auto_units_precision() {
+        assert_eq!(auto(false, 0).size(1536).trim(), "2KiB");
+        assert_eq!(auto(false, 2).size(1536).trim(), "1.50KiB");
+        assert_eq!(auto(false, 3).size(1536).trim(), "1.500KiB");
+    }
+
+    #[test]
+    fn test_auto_units_width_is_stable() {
+        let format = auto(false, 1);
+        let widths: Vec<usize> = [0.0, 9.0, 1000.0, 10_240.0, 1_048_576.0, 900_000_000.0]
+            .iter()
+            .map(|&rate| format.rate(rate).len())
+            .collect();
+
+        assert!(widths.windows(2).all(|w| w[0] == w[1]), "{widths:?}");
+        assert_eq!(format.rate(9.0), "     9.0B/s");
+    }
+}
@@ -35,60 +35,64 @@ const TIME_UNITS: &[TimeUnit] = &[
     },
 ];
 
-/// Formats an uptime duration as a sketchybar key/value pair into `buf`.
+/// Formats a duration using the time units named in `units` (`week`, `day`,
+/// `hour`, `min` and `sec`), e.g. `1h 30m`.
 ///
 /// Units are emitted in descending order of size (week to sec). An empty
-/// `flags` slice selects every unit. When no unit qualifies (for example all
-/// requested flags are unknown, or the duration is zero seconds), the smallest
+/// `units` slice selects every unit. When no unit qualifies (for example all
+/// requested units are unknown, or the duration is zero seconds), the smallest
 /// qualifying unit falls back to a zero value.
-fn format_uptime(uptime_secs: u64, flags: &[&str], buf: &mut String) {
-    let mut uptime_secs = uptime_secs;
+pub(super) fn format_duration(secs: u64, units: &[&str]) -> String {
+    let mut secs = secs;
 
-    let sorted_flags: Vec<&str> = if flags.is_empty() {
+    let sorted_units: Vec<&str> = if units.is_empty() {
         TIME_UNITS.iter().map(|u| u.name).collect()
     } else {
-        let mut flags_vec: Vec<&str> = flags
+        let mut units_vec: Vec<&str> = units
             .iter()
             .copied()
-            .filter(|&flag| TIME_UNITS.iter().any(|u| u.name == flag))
+            .filter(|&name| TIME_UNITS.iter().any(|u| u.name == name))
             .collect();
 
-        flags_vec.sort_by_key(|&flag| {
+        units_vec.sort_by_key(|&name| {
             TIME_UNITS
                 .iter()
-                .position(|u| u.name == flag)
+                .position(|u| u.name == name)
                 .unwrap_or(usize::MAX)
         });
-        flags_vec
+        units_vec
     };
 
-    let _ = write!(buf, "UPTIME=\"");
-    let mut has_value = false;
+    let mut formatted = String::new();
 
-    for &flag in &sorted_flags {
-        if let Some(unit) = TIME_UNITS.iter().find(|u| u.name == flag)
-            && uptime_secs >= unit.seconds
+    for &name in &sorted_units {
+        if let Some(unit) = TIME_UNITS.iter().find(|u| u.name == name)
+            && secs >= unit.seconds
         {
-            let value = uptime_secs / unit.seconds;
-            uptime_secs %= unit.seconds;
-            if has_value {
-                let _ = write!(buf, " ");
+            let value = secs / unit.seconds;
+            secs %= unit.seconds;
+            if !formatted.is_empty() {
+                formatted.push(' ');
             }
-            let _ = write!(buf, "{}{}", value, unit.suffix);
-            has_value = true;
+            let _ = write!(formatted, "{}{}", value, unit.suffix);
         }
     }
 
-    if !has_value {
-        let min_suffix = sorted_flags
+    if formatted.is_empty() {
+        let min_suffix = sorted_units
             .last()
-            .and_then(|flag| TIME_UNITS.iter().find(|u| u.name == *flag))
+            .and_then(|name| TIME_UNITS.iter().find(|u| u.name == *name))
             .map(|unit| unit.suffix)
             .unwrap_or("s");
-        let _ = write!(buf, "0{}", min_suffix);
+        let _ = write!(formatted, "0{}", min_suffix);
     }
 
-    let _ = write!(buf, "\" ");
+    formatted
+}
+
+/// Formats an uptime duration as a sketchybar key/value pair into `buf`.
+fn format_uptime(uptime_secs: u64, flags: &[&str], buf: &mut String) {
+    let _ = write!(buf, "UPTIME=\"{}\" ", format_duration(uptime_secs, flags));
 }
 
 pub fn get_uptime_stats(flags: &[&str], buf: &mut String) {